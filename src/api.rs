@@ -0,0 +1,320 @@
+use std::path::{Component, Path, PathBuf};
+
+use axum::{
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        FromRequest,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::backup::BackupError;
+use crate::collections::CollectionError;
+use crate::embeddings::EmbeddingError;
+use crate::engine::EngineError;
+use crate::export::ExportError;
+use crate::import::ImportError;
+use crate::index::IndexError;
+use crate::storage::StorageError;
+
+/// Error returned by the HTTP API.
+///
+/// Serialized as `{"error": {"code": ..., "message": ..., "details": ...}}`,
+/// where `code` is a stable machine-readable string and `details` carries
+/// structured context (e.g. expected and actual dimensions).
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Value,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: ErrorPayload<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorPayload<'a> {
+    code: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    details: &'a Value,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: Value::Null,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+}
+
+/// Resolve `name`, a request field naming a server file, inside `root`.
+///
+/// Only plain relative paths are accepted: absolute paths and `..` would let
+/// a client read or write anywhere the server can.
+pub fn confined_path(root: &Path, name: &Path) -> Result<PathBuf, ApiError> {
+    let plain = !name.as_os_str().is_empty()
+        && name.components().all(|c| matches!(c, Component::Normal(_)));
+    if plain {
+        Ok(root.join(name))
+    } else {
+        Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_path",
+            format!(
+                "{} must be a relative path without `..` inside {}",
+                name.display(),
+                root.display()
+            ),
+        ))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            tracing::error!(code = self.code, "{}", self.message);
+        }
+        let body = ErrorBody {
+            error: ErrorPayload {
+                code: self.code,
+                message: &self.message,
+                details: &self.details,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<IndexError> for ApiError {
+    fn from(err: IndexError) -> Self {
+        match err {
+            IndexError::DimMismatch { expected, got } => {
+                ApiError::new(StatusCode::BAD_REQUEST, "dim_mismatch", err.to_string())
+                    .with_details(json!({ "expected": expected, "got": got }))
+            }
+            IndexError::Snapshot(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "index_error",
+                err.to_string(),
+            ),
+            #[cfg(any(test, feature = "fault-injection"))]
+            IndexError::Injected(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "index_error",
+                err.to_string(),
+            ),
+        }
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(err: StorageError) -> Self {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "storage_error",
+            err.to_string(),
+        )
+    }
+}
+
+impl From<EmbeddingError> for ApiError {
+    fn from(err: EmbeddingError) -> Self {
+        let (status, code) = match &err {
+            EmbeddingError::NotConfigured => {
+                (StatusCode::SERVICE_UNAVAILABLE, "embedder_not_configured")
+            }
+            EmbeddingError::Timeout => (StatusCode::GATEWAY_TIMEOUT, "embedder_timeout"),
+            EmbeddingError::Transport(_)
+            | EmbeddingError::Status { .. }
+            | EmbeddingError::InvalidResponse(_) => {
+                (StatusCode::BAD_GATEWAY, "embedder_unavailable")
+            }
+            EmbeddingError::DimMismatch { .. } | EmbeddingError::Other(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "embedding_error")
+            }
+        };
+        let details = match &err {
+            EmbeddingError::Status { status, .. } => json!({ "upstream_status": status }),
+            EmbeddingError::DimMismatch { expected, got } => {
+                json!({ "expected": expected, "got": got })
+            }
+            _ => Value::Null,
+        };
+        ApiError::new(status, code, err.to_string()).with_details(details)
+    }
+}
+
+impl From<EngineError> for ApiError {
+    fn from(err: EngineError) -> Self {
+        match err {
+            EngineError::Index(e) => e.into(),
+            EngineError::Storage(e) => e.into(),
+            EngineError::Embedding(e) => e.into(),
+            EngineError::Snapshot(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "snapshot_error",
+                err.to_string(),
+            ),
+            EngineError::Backup(BackupError::DestinationNotEmpty(_)) => {
+                ApiError::new(StatusCode::CONFLICT, "backup_exists", err.to_string())
+            }
+            EngineError::Backup(BackupError::Invalid(_)) => {
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_backup", err.to_string())
+            }
+            EngineError::Backup(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "backup_error",
+                err.to_string(),
+            ),
+            // A missing file is the caller's mistake; other I/O errors are ours.
+            EngineError::Import(ImportError::Io(ref e))
+                if e.kind() != std::io::ErrorKind::NotFound =>
+            {
+                ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "import_error",
+                    err.to_string(),
+                )
+            }
+            EngineError::Import(_) => {
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_import", err.to_string())
+            }
+            EngineError::Export(ExportError::UnknownFormat(_)) => {
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_export", err.to_string())
+            }
+            EngineError::Export(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "export_error",
+                err.to_string(),
+            ),
+            EngineError::LengthMismatch {
+                field,
+                expected,
+                got,
+            } => ApiError::new(StatusCode::BAD_REQUEST, "length_mismatch", err.to_string())
+                .with_details(json!({ "field": field, "expected": expected, "got": got })),
+            EngineError::InvalidParameter(_) => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_parameter",
+                err.to_string(),
+            ),
+            EngineError::RebuildInProgress => {
+                ApiError::new(StatusCode::CONFLICT, "rebuild_in_progress", err.to_string())
+            }
+            EngineError::RebuildFailed(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "rebuild_failed",
+                err.to_string(),
+            ),
+            EngineError::ConfigMismatch { .. } => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "config_mismatch",
+                err.to_string(),
+            ),
+        }
+    }
+}
+
+impl From<CollectionError> for ApiError {
+    fn from(err: CollectionError) -> Self {
+        match err {
+            CollectionError::InvalidName(_) => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_collection_name",
+                err.to_string(),
+            ),
+            CollectionError::AlreadyExists(_) => {
+                ApiError::new(StatusCode::CONFLICT, "collection_exists", err.to_string())
+            }
+            CollectionError::NotFound(_) => {
+                ApiError::not_found("collection_not_found", err.to_string())
+            }
+            CollectionError::Engine(e) => e.into(),
+            CollectionError::Io(_) | CollectionError::Catalog(_) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "catalog_error",
+                err.to_string(),
+            ),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_request", rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_request", rejection.body_text())
+    }
+}
+
+/// `Json` extractor whose rejections are reported as `ApiError`s.
+#[derive(Debug, FromRequest)]
+#[from_request(via(Json), rejection(ApiError))]
+pub struct ApiJson<T>(pub T);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engine_errors_map_to_status_codes() {
+        let dim: ApiError = EngineError::from(IndexError::DimMismatch {
+            expected: 4,
+            got: 3,
+        })
+        .into();
+        assert_eq!(dim.status, StatusCode::BAD_REQUEST);
+        assert_eq!(dim.code, "dim_mismatch");
+        assert_eq!(dim.details, json!({ "expected": 4, "got": 3 }));
+
+        let sqlite: ApiError =
+            EngineError::from(StorageError::Sqlite(rusqlite::Error::InvalidQuery)).into();
+        assert_eq!(sqlite.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(sqlite.code, "storage_error");
+
+        let backup: ApiError =
+            EngineError::from(BackupError::Invalid("no backup.json".to_string())).into();
+        assert_eq!(backup.status, StatusCode::BAD_REQUEST);
+        assert_eq!(backup.code, "invalid_backup");
+
+        let missing: ApiError = CollectionError::NotFound("docs".to_string()).into();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn confined_paths_stay_inside_their_root() {
+        let root = Path::new("/srv/backups");
+        assert_eq!(
+            confined_path(root, Path::new("nightly/2024-06-01")).unwrap(),
+            root.join("nightly/2024-06-01")
+        );
+        for name in ["", "/etc", "../data", "nightly/../../etc", "./x"] {
+            let err = confined_path(root, Path::new(name)).unwrap_err();
+            assert_eq!(err.code, "invalid_path", "{name:?}");
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::index::{IndexConfig, Metric};
+use crate::segments::SegmentedIndex;
+use crate::snapshot::{self, SnapshotError};
+use crate::storage::{SqliteVectorStore, StorageConfig, StorageError};
+
+const MANIFEST_FILE: &str = "backup.json";
+const DATABASE_FILE: &str = "vectors.sqlite";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid backup manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("snapshot error: {0}")]
+    Snapshot(#[from] SnapshotError),
+
+    #[error("backup destination {0} is not empty")]
+    DestinationNotEmpty(PathBuf),
+
+    #[error("invalid backup: {0}")]
+    Invalid(String),
+}
+
+/// Describes a backup bundle: a directory holding a copy of the SQLite
+/// database and an index snapshot covering exactly the same writes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    /// Unix time in seconds.
+    pub created_at: u64,
+    /// Storage write sequence number the backup covers.
+    pub seq: u64,
+    pub dim: usize,
+    pub metric: Metric,
+    /// Vectors in the backup.
+    pub points: usize,
+}
+
+/// Database file inside a bundle; its index snapshot sits next to it.
+pub fn database_path(bundle: &Path) -> PathBuf {
+    bundle.join(DATABASE_FILE)
+}
+
+/// Write a backup of `store` and `index`, which must hold the same writes,
+/// to the new or empty directory `dest`.
+///
+/// The database is copied with SQLite's online backup API, so other
+/// connections may keep reading and writing meanwhile. The manifest is
+/// written last; a bundle without one is incomplete.
+pub fn create(
+    dest: &Path,
+    store: &SqliteVectorStore,
+    index: &SegmentedIndex,
+) -> Result<BackupManifest, BackupError> {
+    std::fs::create_dir_all(dest)?;
+    if std::fs::read_dir(dest)?.next().is_some() {
+        return Err(BackupError::DestinationNotEmpty(dest.to_path_buf()));
+    }
+
+    let seq = store.write_seq()?;
+    let database = database_path(dest);
+    store.backup_to(&database)?;
+    let info = snapshot::save(&snapshot::snapshot_dir(&database), index, seq)?;
+
+    let manifest = BackupManifest {
+        version: FORMAT_VERSION,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        seq,
+        dim: index.dim(),
+        metric: index.metric(),
+        points: info.points,
+    };
+    let tmp = dest.join(format!("{MANIFEST_FILE}.tmp"));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&manifest)?)?;
+    std::fs::rename(&tmp, dest.join(MANIFEST_FILE))?;
+    Ok(manifest)
+}
+
+/// Check that the bundle in `dir` is complete and intact and matches `cfg`,
+/// and load its index.
+///
+/// The database must pass SQLite's integrity check and every row its
+/// checksum, and the snapshot must cover exactly the database's writes.
+/// Any file in the bundle that cannot be read as expected makes it
+/// `BackupError::Invalid`.
+pub fn validate(
+    dir: &Path,
+    cfg: &IndexConfig,
+) -> Result<(BackupManifest, SegmentedIndex), BackupError> {
+    check(dir, cfg).map_err(|e| match e {
+        BackupError::Manifest(_) | BackupError::Storage(_) | BackupError::Snapshot(_) => {
+            BackupError::Invalid(e.to_string())
+        }
+        e => e,
+    })
+}
+
+fn check(dir: &Path, cfg: &IndexConfig) -> Result<(BackupManifest, SegmentedIndex), BackupError> {
+    let manifest: BackupManifest = match std::fs::read(dir.join(MANIFEST_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(BackupError::Invalid(format!(
+                "no {MANIFEST_FILE} in {}",
+                dir.display()
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if manifest.version != FORMAT_VERSION {
+        return Err(BackupError::Invalid(format!(
+            "unsupported backup version {}",
+            manifest.version
+        )));
+    }
+    if manifest.dim != cfg.dim || manifest.metric != cfg.metric {
+        return Err(BackupError::Invalid(format!(
+            "backup is for dim {} / {}, configured dim {} / {}",
+            manifest.dim, manifest.metric, cfg.dim, cfg.metric
+        )));
+    }
+
+    let database = database_path(dir);
+    if !database.is_file() {
+        return Err(BackupError::Invalid(format!(
+            "{} is missing",
+            database.display()
+        )));
+    }
+    let store = SqliteVectorStore::open_read_only(&StorageConfig {
+        path: database.clone(),
+        dim: cfg.dim,
+    })?;
+    if let Some(problem) = store.integrity_errors()?.first() {
+        return Err(BackupError::Invalid(format!(
+            "database is damaged: {problem}"
+        )));
+    }
+    let seq = store.write_seq()?;
+    if seq != manifest.seq {
+        return Err(BackupError::Invalid(format!(
+            "database is at write {seq}, manifest says {}",
+            manifest.seq
+        )));
+    }
+    let (checked, corrupt) = store.verify_rows()?;
+    if !corrupt.is_empty() {
+        return Err(BackupError::Invalid(format!(
+            "{} of {checked} rows fail their checksum",
+            corrupt.len()
+        )));
+    }
+
+    let Some((index, snapshot_seq)) = snapshot::load(&snapshot::snapshot_dir(&database), cfg)?
+    else {
+        return Err(BackupError::Invalid(
+            "index snapshot is missing".to_string(),
+        ));
+    };
+    if snapshot_seq != seq || index.len() != checked || checked != manifest.points {
+        return Err(BackupError::Invalid(format!(
+            "snapshot holds {} vectors at write {snapshot_seq}, database {checked} at write {seq}",
+            index.len()
+        )));
+    }
+    Ok((manifest, index))
+}
@@ -0,0 +1,419 @@
+//! Offline administration of a data directory: the same engine and storage
+//! the server uses, driven from the command line. Stop the server first;
+//! both would otherwise write the same index snapshot.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use self_healing_vector_db::collections::{CollectionCatalog, CollectionError};
+use self_healing_vector_db::config::{CliArgs, Config};
+use self_healing_vector_db::engine::SearchParams;
+use self_healing_vector_db::export::{self, ExportFormat};
+use self_healing_vector_db::filter::Filter;
+use self_healing_vector_db::health::{ConsistencyReport, HealthReport};
+use self_healing_vector_db::import::{ImportFormat, ImportOptions, ImportReport};
+use self_healing_vector_db::snapshot::{snapshot_dir, SnapshotInfo};
+use self_healing_vector_db::storage::{SqliteVectorStore, StorageConfig, SCHEMA_VERSION};
+use self_healing_vector_db::{EngineConfig, Metric, SelfHealingVectorDb};
+use tracing_subscriber::EnvFilter;
+
+/// Inspect, verify and maintain a vector database without a running server.
+///
+/// The store is found the way the server finds it: from `--config`, the
+/// `VECTOR_DB_*` environment variables and the flags below. Results are
+/// printed as JSON.
+#[derive(Debug, Parser)]
+#[command(name = "self_healing_vector_db_admin", version)]
+struct AdminArgs {
+    /// TOML configuration file, as read by the server.
+    #[arg(long, env = "VECTOR_DB_CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_DATA_DIR", global = true)]
+    data_dir: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_STORAGE_PATH", global = true)]
+    storage_path: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_DIM", global = true)]
+    dim: Option<usize>,
+
+    /// One of l2, cosine, dot, l1, hamming.
+    #[arg(long, env = "VECTOR_DB_METRIC", global = true)]
+    metric: Option<Metric>,
+
+    /// Work on this collection of the data directory instead of the default
+    /// store.
+    #[arg(long, global = true)]
+    collection: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show row counts, vector dimensions, the schema version and file
+    /// sizes. Reads the database only; the index is not loaded.
+    Inspect,
+
+    /// Verify every row's checksum, then compare storage with the index in
+    /// full. Exits with status 1 if anything is wrong.
+    Verify,
+
+    /// Rebuild the index from storage and save a snapshot of it.
+    Rebuild,
+
+    /// Import vectors from a `.fvecs`, `.bvecs`, `.ivecs`, `.npy` or
+    /// `.jsonl` file.
+    Import {
+        path: PathBuf,
+
+        /// Input format; by default the file extension decides.
+        #[arg(long)]
+        format: Option<ImportFormat>,
+
+        /// Id of the first vector in formats without ids.
+        #[arg(long)]
+        first_id: Option<i64>,
+
+        /// Vectors written per storage transaction.
+        #[arg(long)]
+        batch_size: Option<usize>,
+    },
+
+    /// Export every vector to a new `.jsonl`, `.npy` or `.fvecs` file.
+    /// Reads the database only; the index is not loaded.
+    Export {
+        path: PathBuf,
+
+        /// Output format; by default the file extension decides.
+        #[arg(long)]
+        format: Option<ExportFormat>,
+    },
+
+    /// Write a backup bundle, a copy of the database and a matching index
+    /// snapshot, to a new or empty directory.
+    Backup { dest: PathBuf },
+
+    /// Replace the store with a backup bundle. The bundle is validated in
+    /// full first; writes made since the backup are lost.
+    Restore { source: PathBuf },
+
+    /// Compact sparse index segments and save a snapshot.
+    Compact,
+
+    /// Find the nearest neighbours of a vector.
+    Search {
+        /// Comma-separated components, e.g. `0.1,-2,3.5`.
+        #[arg(
+            long,
+            required = true,
+            value_delimiter = ',',
+            allow_hyphen_values = true
+        )]
+        vector: Vec<f32>,
+
+        #[arg(short, default_value_t = 10)]
+        k: usize,
+
+        /// Payload filter as JSON, as in the search API.
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Attach each result's stored payload.
+        #[arg(long)]
+        with_payload: bool,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct InspectReport {
+    storage_path: PathBuf,
+    schema_version: u32,
+    /// Metric recorded when the store was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metric: Option<String>,
+    /// Configured vector dimension.
+    dim: usize,
+    rows: usize,
+    /// Rows with a vector of the configured dimension.
+    loadable: usize,
+    /// Rows per stored vector dimension.
+    dims: BTreeMap<usize, usize>,
+    write_seq: u64,
+    /// Missing from databases no server has migrated yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quarantined: Option<usize>,
+    /// Bytes on disk of the database, its journal files and the snapshot.
+    files: BTreeMap<PathBuf, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyReport {
+    ok: bool,
+    /// Rows whose checksum was verified.
+    checked: usize,
+    /// Ids of the rows that failed. Opening the index quarantines them
+    /// unless it is loaded from a snapshot.
+    corrupt: Vec<i64>,
+    /// Problems found by SQLite's own integrity check.
+    integrity_errors: Vec<String>,
+    consistency: ConsistencyReport,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexReport {
+    size: usize,
+    slots: usize,
+    segments: usize,
+    quarantined: usize,
+    /// `None` if the snapshot on disk was already current.
+    snapshot: Option<SnapshotInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportOutcome {
+    #[serde(flatten)]
+    import: ImportReport,
+    index: IndexReport,
+}
+
+fn main() {
+    let args = AdminArgs::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cfg = engine_config(&args).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(2);
+    });
+    match run(args.command, cfg) {
+        Ok((report, ok)) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Settings for the store the command works on, which must already exist.
+fn engine_config(args: &AdminArgs) -> Result<EngineConfig, Box<dyn Error>> {
+    let config = Config::load(&CliArgs {
+        config: args.config.clone(),
+        data_dir: args.data_dir.clone(),
+        storage_path: args.storage_path.clone(),
+        dim: args.dim,
+        metric: args.metric,
+        ..CliArgs::default()
+    })?;
+    let cfg = match &args.collection {
+        Some(name) => {
+            let catalog = CollectionCatalog::open(&config.server.data_dir)?;
+            let collection = catalog
+                .get(name)
+                .ok_or_else(|| CollectionError::NotFound(name.clone()))?;
+            collection.engine_config(catalog.storage_path(name))
+        }
+        None => config.engine_config(),
+    };
+    if !cfg.storage_path.is_file() {
+        return Err(format!("no database at {}", cfg.storage_path.display()).into());
+    }
+    Ok(cfg)
+}
+
+/// Run `command`, returning its report and whether it found the store
+/// healthy.
+fn run(command: Command, cfg: EngineConfig) -> Result<(serde_json::Value, bool), Box<dyn Error>> {
+    let report = match command {
+        Command::Inspect => serde_json::to_value(inspect(&cfg)?)?,
+        Command::Verify => {
+            let report = verify(cfg)?;
+            let ok = report.ok;
+            return Ok((serde_json::to_value(report)?, ok));
+        }
+        Command::Rebuild => {
+            let mut engine = SelfHealingVectorDb::new(cfg, None)?;
+            engine.rebuild_index()?;
+            serde_json::to_value(index_report(&engine)?)?
+        }
+        Command::Import {
+            path,
+            format,
+            first_id,
+            batch_size,
+        } => {
+            let defaults = ImportOptions::default();
+            let options = ImportOptions {
+                format,
+                first_id: first_id.unwrap_or(defaults.first_id),
+                batch_size: batch_size.unwrap_or(defaults.batch_size),
+            };
+            let mut engine = SelfHealingVectorDb::new(cfg, None)?;
+            let import = engine.import_file(&path, &options)?;
+            serde_json::to_value(ImportOutcome {
+                import,
+                index: index_report(&engine)?,
+            })?
+        }
+        Command::Export { path, format } => {
+            let format = match format {
+                Some(format) => format,
+                None => ExportFormat::from_path(&path)?,
+            };
+            let store = open_read_only(&cfg)?;
+            let out = BufWriter::new(File::create(&path)?);
+            serde_json::to_value(export::write(&store, format, out)?)?
+        }
+        Command::Backup { dest } => {
+            let engine = SelfHealingVectorDb::new(cfg, None)?;
+            serde_json::to_value(engine.backup(&dest)?)?
+        }
+        Command::Restore { source } => {
+            let mut engine = SelfHealingVectorDb::new(cfg, None)?;
+            serde_json::to_value(engine.restore_backup(&source)?)?
+        }
+        Command::Compact => {
+            let mut engine = SelfHealingVectorDb::new(cfg, None)?;
+            engine.maintain();
+            // Each finished compaction may leave another one to start.
+            while engine.is_compacting() {
+                std::thread::sleep(Duration::from_millis(50));
+                engine.maintain();
+            }
+            serde_json::to_value(index_report(&engine)?)?
+        }
+        Command::Search {
+            vector,
+            k,
+            filter,
+            with_payload,
+        } => {
+            let params = SearchParams {
+                with_payload,
+                filter: filter
+                    .as_deref()
+                    .map(serde_json::from_str::<Filter>)
+                    .transpose()?,
+                ..SearchParams::default()
+            };
+            let engine = SelfHealingVectorDb::new(cfg, None)?;
+            serde_json::to_value(engine.search_with(&vector, k, &params)?)?
+        }
+    };
+    Ok((report, true))
+}
+
+fn open_read_only(cfg: &EngineConfig) -> Result<SqliteVectorStore, Box<dyn Error>> {
+    Ok(SqliteVectorStore::open_read_only(&StorageConfig {
+        path: cfg.storage_path.clone(),
+        dim: cfg.dim,
+    })?)
+}
+
+fn inspect(cfg: &EngineConfig) -> Result<InspectReport, Box<dyn Error>> {
+    let store = open_read_only(cfg)?;
+    let schema_version = store.schema_version()?;
+    Ok(InspectReport {
+        storage_path: cfg.storage_path.clone(),
+        schema_version,
+        metric: store.meta("metric")?,
+        dim: cfg.dim,
+        rows: store.count()?,
+        loadable: store.count_loadable()?,
+        dims: store.vector_dims()?.into_iter().collect(),
+        write_seq: store.write_seq()?,
+        quarantined: if schema_version >= SCHEMA_VERSION {
+            Some(store.quarantine_count()?)
+        } else {
+            None
+        },
+        files: file_sizes(&cfg.storage_path),
+    })
+}
+
+/// Checksums first, read-only, so corrupt rows are reported before opening
+/// the engine can quarantine them.
+fn verify(cfg: EngineConfig) -> Result<VerifyReport, Box<dyn Error>> {
+    let (checked, corrupt, integrity_errors) = {
+        // Opened writable so databases from older versions are migrated
+        // and have checksums to verify.
+        let store = SqliteVectorStore::new(&StorageConfig {
+            path: cfg.storage_path.clone(),
+            dim: cfg.dim,
+        })?;
+        let (checked, corrupt) = store.verify_rows()?;
+        (checked, corrupt, store.integrity_errors()?)
+    };
+    let engine = SelfHealingVectorDb::new(cfg, None)?;
+    let consistency = engine.verify()?;
+    Ok(VerifyReport {
+        ok: corrupt.is_empty() && integrity_errors.is_empty() && consistency.is_consistent(),
+        checked,
+        corrupt,
+        integrity_errors,
+        consistency,
+    })
+}
+
+/// The state of the index after a change, saved as a snapshot so the next
+/// server start loads it.
+fn index_report(engine: &SelfHealingVectorDb) -> Result<IndexReport, Box<dyn Error>> {
+    let snapshot = engine.snapshot()?;
+    let HealthReport {
+        size,
+        slots,
+        segments,
+        quarantined,
+        ..
+    } = engine.health();
+    Ok(IndexReport {
+        size,
+        slots,
+        segments,
+        quarantined,
+        snapshot,
+    })
+}
+
+/// Sizes of the database, its `-wal` and `-shm` files and the snapshot
+/// directory, where present.
+fn file_sizes(storage_path: &Path) -> BTreeMap<PathBuf, u64> {
+    let suffixed = |suffix: &str| {
+        let mut name = storage_path.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    };
+    let mut sizes = BTreeMap::new();
+    for path in [
+        storage_path.to_path_buf(),
+        suffixed("-wal"),
+        suffixed("-shm"),
+    ] {
+        if let Ok(meta) = std::fs::metadata(&path) {
+            sizes.insert(path, meta.len());
+        }
+    }
+    let dir = snapshot_dir(storage_path);
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        let total = entries
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|meta| meta.len())
+            .sum();
+        sizes.insert(dir, total);
+    }
+    sizes
+}
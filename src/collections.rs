@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::embeddings::SharedEmbedder;
+use crate::engine::{EngineConfig, EngineError, SelfHealingVectorDb};
+use crate::index::Metric;
+use crate::snapshot;
+
+const CATALOG_FILE: &str = "collections.json";
+const COLLECTIONS_DIR: &str = "collections";
+
+#[derive(Debug, Error)]
+pub enum CollectionError {
+    #[error("invalid collection name: {0:?}")]
+    InvalidName(String),
+
+    #[error("collection already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("collection not found: {0}")]
+    NotFound(String),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("catalog error: {0}")]
+    Catalog(#[from] serde_json::Error),
+
+    #[error("engine error: {0}")]
+    Engine(#[from] EngineError),
+}
+
+/// Per-collection settings, persisted in the catalog.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionConfig {
+    pub dim: usize,
+    #[serde(default)]
+    pub metric: Metric,
+    #[serde(default = "default_max_elements")]
+    pub hnsw_max_elements: usize,
+    #[serde(default = "default_mutable_segment_capacity")]
+    pub mutable_segment_capacity: usize,
+    #[serde(default = "default_m")]
+    pub hnsw_m: usize,
+    #[serde(default = "default_ef_construction")]
+    pub hnsw_ef_construction: usize,
+    #[serde(default = "default_ef_search")]
+    pub hnsw_ef_search: usize,
+}
+
+pub(crate) fn default_max_elements() -> usize {
+    100_000
+}
+
+pub(crate) fn default_mutable_segment_capacity() -> usize {
+    10_000
+}
+
+pub(crate) fn default_m() -> usize {
+    16
+}
+
+pub(crate) fn default_ef_construction() -> usize {
+    200
+}
+
+pub(crate) fn default_ef_search() -> usize {
+    64
+}
+
+impl CollectionConfig {
+    pub fn engine_config(&self, storage_path: PathBuf) -> EngineConfig {
+        EngineConfig {
+            dim: self.dim,
+            metric: self.metric,
+            storage_path,
+            hnsw_max_elements: self.hnsw_max_elements,
+            mutable_segment_capacity: self.mutable_segment_capacity,
+            hnsw_m: self.hnsw_m,
+            hnsw_ef_construction: self.hnsw_ef_construction,
+            hnsw_ef_search: self.hnsw_ef_search,
+        }
+    }
+}
+
+/// Registry of named collections under a data directory.
+///
+/// Layout:
+/// - `<root>/collections.json` – name -> `CollectionConfig`
+/// - `<root>/collections/<name>.sqlite` – one `SqliteVectorStore` per collection
+/// - `<root>/collections/<name>.sqlite.snapshot/` – its saved index graph, if any
+///
+/// The catalog only tracks configuration; callers own the opened
+/// `SelfHealingVectorDb` instances.
+pub struct CollectionCatalog {
+    root: PathBuf,
+    configs: BTreeMap<String, CollectionConfig>,
+}
+
+impl CollectionCatalog {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, CollectionError> {
+        let root = root.into();
+        std::fs::create_dir_all(root.join(COLLECTIONS_DIR))?;
+
+        let catalog_path = root.join(CATALOG_FILE);
+        let configs = if catalog_path.exists() {
+            serde_json::from_slice(&std::fs::read(&catalog_path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self { root, configs })
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = (&str, &CollectionConfig)> {
+        self.configs.iter().map(|(name, cfg)| (name.as_str(), cfg))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CollectionConfig> {
+        self.configs.get(name)
+    }
+
+    pub fn storage_path(&self, name: &str) -> PathBuf {
+        self.root
+            .join(COLLECTIONS_DIR)
+            .join(format!("{name}.sqlite"))
+    }
+
+    /// Register a new collection and open its (empty) engine.
+    pub fn create(
+        &mut self,
+        name: &str,
+        cfg: CollectionConfig,
+        embedder: Option<SharedEmbedder>,
+    ) -> Result<SelfHealingVectorDb, CollectionError> {
+        validate_name(name)?;
+        cfg.engine_config(self.storage_path(name)).validate()?;
+        if self.configs.contains_key(name) {
+            return Err(CollectionError::AlreadyExists(name.to_string()));
+        }
+
+        // A file left behind by a crash between drop and catalog write must not
+        // leak old vectors into the new collection.
+        remove_if_exists(&self.storage_path(name))?;
+        snapshot::remove(&snapshot::snapshot_dir(&self.storage_path(name)))?;
+
+        let engine =
+            SelfHealingVectorDb::new(cfg.engine_config(self.storage_path(name)), embedder)?;
+        self.configs.insert(name.to_string(), cfg);
+        self.persist()?;
+        Ok(engine)
+    }
+
+    /// Open the engine for an existing collection, rebuilding its index from storage.
+    pub fn open_collection(
+        &self,
+        name: &str,
+        embedder: Option<SharedEmbedder>,
+    ) -> Result<SelfHealingVectorDb, CollectionError> {
+        let cfg = self
+            .configs
+            .get(name)
+            .ok_or_else(|| CollectionError::NotFound(name.to_string()))?;
+        Ok(SelfHealingVectorDb::new(
+            cfg.engine_config(self.storage_path(name)),
+            embedder,
+        )?)
+    }
+
+    /// Remove a collection from the catalog and delete its data.
+    pub fn drop_collection(&mut self, name: &str) -> Result<(), CollectionError> {
+        if self.configs.remove(name).is_none() {
+            return Err(CollectionError::NotFound(name.to_string()));
+        }
+        self.persist()?;
+        remove_if_exists(&self.storage_path(name))?;
+        snapshot::remove(&snapshot::snapshot_dir(&self.storage_path(name)))?;
+        Ok(())
+    }
+
+    fn persist(&self) -> Result<(), CollectionError> {
+        // Write-then-rename so a crash never leaves a half-written catalog.
+        let path = self.root.join(CATALOG_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.configs)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), CollectionError> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(CollectionError::InvalidName(name.to_string()))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), std::io::Error> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,673 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::collections::{
+    default_ef_construction, default_ef_search, default_m, default_max_elements,
+    default_mutable_segment_capacity,
+};
+use crate::embeddings::{EmbeddingError, HttpEmbedder, HttpEmbedderConfig, SharedEmbedder};
+use crate::engine::{EngineConfig, RecallParams};
+use crate::index::Metric;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("cannot parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid configuration: {0}")]
+    Invalid(String),
+}
+
+/// Effective server configuration.
+///
+/// Built from, in increasing order of precedence: built-in defaults, a TOML
+/// file (`--config`), `VECTOR_DB_*` environment variables and command-line flags.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerSettings,
+    pub engine: EngineSettings,
+    pub embedder: EmbedderSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub bind: SocketAddr,
+    /// Holds the collection catalog and, unless overridden, the default store.
+    pub data_dir: PathBuf,
+    /// How often to save index snapshots for faster restarts; 0 disables them.
+    pub snapshot_interval_secs: u64,
+    /// How often to check each index against storage and repair it; 0 disables the check.
+    pub consistency_check_interval_secs: u64,
+    /// How often to verify every stored row's checksum and quarantine
+    /// corrupt ones; 0 disables the scrub.
+    pub scrub_interval_secs: u64,
+    /// How often to estimate each index's recall; 0 disables the estimate.
+    pub recall_check_interval_secs: u64,
+    /// Neighbours compared per sample query.
+    pub recall_k: usize,
+    /// Sample queries per estimate.
+    pub recall_queries: usize,
+    /// Recall below this reports the index as degraded in `/health`.
+    pub min_recall: f32,
+    /// Rebuild an index whose estimated recall falls below this; unset never rebuilds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rebuild_below_recall: Option<f32>,
+    /// Where `/admin/backup` writes bundles and the restore routes read
+    /// them; defaults to `<data_dir>/backups`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<PathBuf>,
+    /// Files `/admin/import` may load; defaults to `<data_dir>/import`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_dir: Option<PathBuf>,
+    /// Bearer token the `/admin` routes require. Unset, they only answer
+    /// clients on the loopback interface. Never printed by `--print-config`.
+    #[serde(skip_serializing)]
+    pub admin_token: Option<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([127, 0, 0, 1], 3000)),
+            data_dir: PathBuf::from("data"),
+            snapshot_interval_secs: 300,
+            consistency_check_interval_secs: 600,
+            scrub_interval_secs: 3600,
+            recall_check_interval_secs: 900,
+            recall_k: 10,
+            recall_queries: 32,
+            min_recall: 0.9,
+            rebuild_below_recall: None,
+            backup_dir: None,
+            import_dir: None,
+            admin_token: None,
+        }
+    }
+}
+
+/// Settings for the default (unscoped) engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineSettings {
+    pub dim: usize,
+    pub metric: Metric,
+    /// Defaults to `<data_dir>/vectors.sqlite`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_path: Option<PathBuf>,
+    /// Slots per sealed index segment.
+    pub hnsw_max_elements: usize,
+    /// Slots the mutable segment fills before it is sealed; capped at
+    /// `hnsw_max_elements`.
+    pub mutable_segment_capacity: usize,
+    pub hnsw_m: usize,
+    pub hnsw_ef_construction: usize,
+    pub hnsw_ef_search: usize,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            dim: 384,
+            metric: Metric::L2,
+            storage_path: None,
+            hnsw_max_elements: default_max_elements(),
+            mutable_segment_capacity: default_mutable_segment_capacity(),
+            hnsw_m: default_m(),
+            hnsw_ef_construction: default_ef_construction(),
+            hnsw_ef_search: default_ef_search(),
+        }
+    }
+}
+
+/// Backend behind `/add_text` and `/search_text`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedderKind {
+    /// Zero vectors; only useful for trying the text routes out.
+    #[default]
+    Dummy,
+    /// A local ONNX model; needs a build with `--features onnx`.
+    Onnx,
+    /// An OpenAI-compatible `/v1/embeddings` API.
+    Http,
+}
+
+impl std::str::FromStr for EmbedderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dummy" => Ok(EmbedderKind::Dummy),
+            "onnx" => Ok(EmbedderKind::Onnx),
+            "http" => Ok(EmbedderKind::Http),
+            other => Err(format!("unknown embedder: {other}")),
+        }
+    }
+}
+
+/// The embedder shared by the default store and every collection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbedderSettings {
+    pub kind: EmbedderKind,
+    /// `onnx`: directory holding `tokenizer.json` and `model.onnx`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_path: Option<PathBuf>,
+    /// `http`: server root, e.g. `https://api.openai.com/v1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `http`: model name sent with every request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// `http`: bearer token; never printed by `--print-config`.
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    /// `http`: deadline for a single request.
+    pub timeout_secs: u64,
+}
+
+impl Default for EmbedderSettings {
+    fn default() -> Self {
+        Self {
+            kind: EmbedderKind::Dummy,
+            model_path: None,
+            url: None,
+            model: None,
+            api_key: None,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl EmbedderSettings {
+    /// The configured embedder, or `None` for `dummy`, whose dimension
+    /// depends on the store it serves.
+    pub fn build(&self) -> Result<Option<SharedEmbedder>, EmbeddingError> {
+        match self.kind {
+            EmbedderKind::Dummy => Ok(None),
+            EmbedderKind::Http => {
+                let url = self.url.clone().ok_or(EmbeddingError::NotConfigured)?;
+                let model = self.model.clone().ok_or(EmbeddingError::NotConfigured)?;
+                let mut cfg = HttpEmbedderConfig::new(url, model);
+                cfg.api_key = self.api_key.clone();
+                cfg.timeout = Duration::from_secs(self.timeout_secs);
+                Ok(Some(Arc::new(HttpEmbedder::new(cfg)?)))
+            }
+            #[cfg(feature = "onnx")]
+            EmbedderKind::Onnx => {
+                let dir = self
+                    .model_path
+                    .clone()
+                    .ok_or(EmbeddingError::NotConfigured)?;
+                let cfg = crate::embeddings::OnnxEmbedderConfig::new(dir);
+                Ok(Some(Arc::new(crate::embeddings::OnnxEmbedder::new(cfg)?)))
+            }
+            #[cfg(not(feature = "onnx"))]
+            EmbedderKind::Onnx => Err(EmbeddingError::Other(
+                "built without the onnx feature".to_string(),
+            )),
+        }
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self.kind {
+            EmbedderKind::Dummy => {}
+            EmbedderKind::Onnx => {
+                if cfg!(not(feature = "onnx")) {
+                    problems.push(
+                        "embedder.kind = \"onnx\" needs a build with --features onnx".to_string(),
+                    );
+                }
+                if self.model_path.is_none() {
+                    problems
+                        .push("embedder.model_path is required for the onnx embedder".to_string());
+                }
+            }
+            EmbedderKind::Http => {
+                for (name, value) in [("embedder.url", &self.url), ("embedder.model", &self.model)]
+                {
+                    if value.as_deref().unwrap_or("").is_empty() {
+                        problems.push(format!("{name} is required for the http embedder"));
+                    }
+                }
+                if self.timeout_secs == 0 {
+                    problems.push("embedder.timeout_secs must be greater than 0".to_string());
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Command-line flags; each one can also be set through the named env var.
+#[derive(Debug, Default, Parser)]
+#[command(name = "self_healing_vector_db_server", version, about)]
+pub struct CliArgs {
+    /// TOML configuration file.
+    #[arg(long, env = "VECTOR_DB_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Print the effective configuration as TOML and exit.
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[arg(long, env = "VECTOR_DB_BIND")]
+    pub bind: Option<SocketAddr>,
+
+    #[arg(long, env = "VECTOR_DB_DATA_DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Seconds between index snapshots; 0 disables them.
+    #[arg(long, env = "VECTOR_DB_SNAPSHOT_INTERVAL_SECS")]
+    pub snapshot_interval_secs: Option<u64>,
+
+    /// Seconds between background consistency checks; 0 disables them.
+    #[arg(long, env = "VECTOR_DB_CONSISTENCY_CHECK_INTERVAL_SECS")]
+    pub consistency_check_interval_secs: Option<u64>,
+
+    /// Seconds between background checksum scrubs; 0 disables them.
+    #[arg(long, env = "VECTOR_DB_SCRUB_INTERVAL_SECS")]
+    pub scrub_interval_secs: Option<u64>,
+
+    /// Seconds between background recall estimates; 0 disables them.
+    #[arg(long, env = "VECTOR_DB_RECALL_CHECK_INTERVAL_SECS")]
+    pub recall_check_interval_secs: Option<u64>,
+
+    #[arg(long, env = "VECTOR_DB_RECALL_K")]
+    pub recall_k: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_RECALL_QUERIES")]
+    pub recall_queries: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_MIN_RECALL")]
+    pub min_recall: Option<f32>,
+
+    #[arg(long, env = "VECTOR_DB_REBUILD_BELOW_RECALL")]
+    pub rebuild_below_recall: Option<f32>,
+
+    #[arg(long, env = "VECTOR_DB_BACKUP_DIR")]
+    pub backup_dir: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_IMPORT_DIR")]
+    pub import_dir: Option<PathBuf>,
+
+    /// Prefer the env var, which keeps the token out of the process list.
+    #[arg(long, env = "VECTOR_DB_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+
+    #[arg(long, env = "VECTOR_DB_STORAGE_PATH")]
+    pub storage_path: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_DIM")]
+    pub dim: Option<usize>,
+
+    /// One of l2, cosine, dot, l1, hamming.
+    #[arg(long, env = "VECTOR_DB_METRIC")]
+    pub metric: Option<Metric>,
+
+    #[arg(long, env = "VECTOR_DB_HNSW_MAX_ELEMENTS")]
+    pub hnsw_max_elements: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_MUTABLE_SEGMENT_CAPACITY")]
+    pub mutable_segment_capacity: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_HNSW_M")]
+    pub hnsw_m: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_HNSW_EF_CONSTRUCTION")]
+    pub hnsw_ef_construction: Option<usize>,
+
+    #[arg(long, env = "VECTOR_DB_HNSW_EF_SEARCH")]
+    pub hnsw_ef_search: Option<usize>,
+
+    /// One of dummy, onnx, http.
+    #[arg(long, env = "VECTOR_DB_EMBEDDER")]
+    pub embedder: Option<EmbedderKind>,
+
+    #[arg(long, env = "VECTOR_DB_EMBEDDER_MODEL_PATH")]
+    pub embedder_model_path: Option<PathBuf>,
+
+    #[arg(long, env = "VECTOR_DB_EMBEDDER_URL")]
+    pub embedder_url: Option<String>,
+
+    #[arg(long, env = "VECTOR_DB_EMBEDDER_MODEL")]
+    pub embedder_model: Option<String>,
+
+    /// Prefer the env var, which keeps the key out of the process list.
+    #[arg(long, env = "VECTOR_DB_EMBEDDER_API_KEY", hide_env_values = true)]
+    pub embedder_api_key: Option<String>,
+
+    #[arg(long, env = "VECTOR_DB_EMBEDDER_TIMEOUT_SECS")]
+    pub embedder_timeout_secs: Option<u64>,
+}
+
+impl Config {
+    /// Resolve the effective configuration for `args` and validate it.
+    pub fn load(args: &CliArgs) -> Result<Self, ConfigError> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply(args);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Overlay the flags (or env vars) that were given.
+    pub fn apply(&mut self, args: &CliArgs) {
+        let server = &mut self.server;
+        let engine = &mut self.engine;
+        if let Some(bind) = args.bind {
+            server.bind = bind;
+        }
+        if let Some(dir) = &args.data_dir {
+            server.data_dir = dir.clone();
+        }
+        if let Some(secs) = args.snapshot_interval_secs {
+            server.snapshot_interval_secs = secs;
+        }
+        if let Some(secs) = args.consistency_check_interval_secs {
+            server.consistency_check_interval_secs = secs;
+        }
+        if let Some(secs) = args.scrub_interval_secs {
+            server.scrub_interval_secs = secs;
+        }
+        if let Some(secs) = args.recall_check_interval_secs {
+            server.recall_check_interval_secs = secs;
+        }
+        if let Some(k) = args.recall_k {
+            server.recall_k = k;
+        }
+        if let Some(queries) = args.recall_queries {
+            server.recall_queries = queries;
+        }
+        if let Some(min) = args.min_recall {
+            server.min_recall = min;
+        }
+        if args.rebuild_below_recall.is_some() {
+            server.rebuild_below_recall = args.rebuild_below_recall;
+        }
+        if let Some(dir) = &args.backup_dir {
+            server.backup_dir = Some(dir.clone());
+        }
+        if let Some(dir) = &args.import_dir {
+            server.import_dir = Some(dir.clone());
+        }
+        if let Some(token) = &args.admin_token {
+            server.admin_token = Some(token.clone());
+        }
+        if let Some(path) = &args.storage_path {
+            engine.storage_path = Some(path.clone());
+        }
+        if let Some(metric) = args.metric {
+            engine.metric = metric;
+        }
+        for (value, target) in [
+            (args.dim, &mut engine.dim),
+            (args.hnsw_max_elements, &mut engine.hnsw_max_elements),
+            (
+                args.mutable_segment_capacity,
+                &mut engine.mutable_segment_capacity,
+            ),
+            (args.hnsw_m, &mut engine.hnsw_m),
+            (args.hnsw_ef_construction, &mut engine.hnsw_ef_construction),
+            (args.hnsw_ef_search, &mut engine.hnsw_ef_search),
+        ] {
+            if let Some(value) = value {
+                *target = value;
+            }
+        }
+
+        let embedder = &mut self.embedder;
+        if let Some(kind) = args.embedder {
+            embedder.kind = kind;
+        }
+        if let Some(path) = &args.embedder_model_path {
+            embedder.model_path = Some(path.clone());
+        }
+        for (value, target) in [
+            (&args.embedder_url, &mut embedder.url),
+            (&args.embedder_model, &mut embedder.model),
+            (&args.embedder_api_key, &mut embedder.api_key),
+        ] {
+            if value.is_some() {
+                *target = value.clone();
+            }
+        }
+        if let Some(secs) = args.embedder_timeout_secs {
+            embedder.timeout_secs = secs;
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems: Vec<String> = self
+            .engine_config()
+            .problems()
+            .into_iter()
+            .map(|problem| format!("engine.{problem}"))
+            .collect();
+        for (name, value) in [
+            ("server.recall_k", self.server.recall_k),
+            ("server.recall_queries", self.server.recall_queries),
+        ] {
+            if value == 0 {
+                problems.push(format!("{name} must be greater than 0"));
+            }
+        }
+        for (name, value) in [
+            ("server.min_recall", Some(self.server.min_recall)),
+            (
+                "server.rebuild_below_recall",
+                self.server.rebuild_below_recall,
+            ),
+        ] {
+            if let Some(value) = value.filter(|v| !(0.0..=1.0).contains(v)) {
+                problems.push(format!("{name} must be between 0 and 1, got {value}"));
+            }
+        }
+        if self.server.data_dir.as_os_str().is_empty() {
+            problems.push("server.data_dir must not be empty".to_string());
+        }
+        if self.server.admin_token.as_deref() == Some("") {
+            problems.push("server.admin_token must not be empty".to_string());
+        }
+        problems.extend(self.embedder.problems());
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems.join("; ")))
+        }
+    }
+
+    pub fn storage_path(&self) -> PathBuf {
+        self.engine
+            .storage_path
+            .clone()
+            .unwrap_or_else(|| self.server.data_dir.join("vectors.sqlite"))
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.server
+            .backup_dir
+            .clone()
+            .unwrap_or_else(|| self.server.data_dir.join("backups"))
+    }
+
+    pub fn import_dir(&self) -> PathBuf {
+        self.server
+            .import_dir
+            .clone()
+            .unwrap_or_else(|| self.server.data_dir.join("import"))
+    }
+
+    pub fn engine_config(&self) -> EngineConfig {
+        EngineConfig {
+            dim: self.engine.dim,
+            metric: self.engine.metric,
+            storage_path: self.storage_path(),
+            hnsw_max_elements: self.engine.hnsw_max_elements,
+            mutable_segment_capacity: self.engine.mutable_segment_capacity,
+            hnsw_m: self.engine.hnsw_m,
+            hnsw_ef_construction: self.engine.hnsw_ef_construction,
+            hnsw_ef_search: self.engine.hnsw_ef_search,
+        }
+    }
+
+    pub fn recall_params(&self) -> RecallParams {
+        RecallParams {
+            k: self.server.recall_k,
+            queries: self.server.recall_queries,
+            min_recall: self.server.min_recall,
+        }
+    }
+
+    /// The configuration as TOML, with `storage_path` and the directories resolved.
+    pub fn to_toml(&self) -> String {
+        let mut resolved = self.clone();
+        resolved.engine.storage_path = Some(self.storage_path());
+        resolved.server.backup_dir = Some(self.backup_dir());
+        resolved.server.import_dir = Some(self.import_dir());
+        toml::to_string_pretty(&resolved).expect("config is always representable as TOML")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_the_previous_hardcoded_server() {
+        let config = Config::load(&CliArgs::default()).expect("defaults are valid");
+        assert_eq!(config.server.bind.to_string(), "127.0.0.1:3000");
+        assert_eq!(config.storage_path(), PathBuf::from("data/vectors.sqlite"));
+        assert_eq!(config.backup_dir(), PathBuf::from("data/backups"));
+        assert_eq!(config.import_dir(), PathBuf::from("data/import"));
+        assert_eq!(config.server.admin_token, None);
+        assert_eq!(config.engine.dim, 384);
+        assert_eq!(config.engine.hnsw_m, 16);
+    }
+
+    #[test]
+    fn flags_override_file_values() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [server]
+            bind = "0.0.0.0:8080"
+            data_dir = "/var/lib/vectors"
+
+            [engine]
+            dim = 768
+            metric = "cosine"
+            hnsw_m = 32
+            "#,
+        )
+        .expect("valid toml");
+
+        let args = CliArgs::try_parse_from(["server", "--dim", "1024", "--hnsw-ef-search", "128"])
+            .expect("valid flags");
+        config.apply(&args);
+        config.validate().expect("valid config");
+
+        let engine = config.engine_config();
+        assert_eq!(engine.dim, 1024);
+        assert_eq!(engine.metric, Metric::Cosine);
+        assert_eq!(engine.hnsw_m, 32);
+        assert_eq!(engine.hnsw_ef_search, 128);
+        assert_eq!(
+            engine.storage_path,
+            PathBuf::from("/var/lib/vectors/vectors.sqlite")
+        );
+        assert_eq!(config.server.bind.port(), 8080);
+
+        let printed: Config = toml::from_str(&config.to_toml()).expect("printed config parses");
+        assert_eq!(printed.storage_path(), config.storage_path());
+    }
+
+    #[test]
+    fn embedder_comes_from_the_file_and_flags() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [embedder]
+            kind = "http"
+            url = "http://localhost:8080"
+            model = "bge-small"
+            api_key = "secret"
+            "#,
+        )
+        .expect("valid toml");
+        let args = CliArgs::try_parse_from(["server", "--embedder-timeout-secs", "5"])
+            .expect("valid flags");
+        config.apply(&args);
+        config.validate().expect("valid config");
+
+        assert_eq!(config.embedder.kind, EmbedderKind::Http);
+        assert_eq!(config.embedder.timeout_secs, 5);
+        assert!(config.embedder.build().expect("http embedder").is_some());
+        assert!(!config.to_toml().contains("secret"));
+
+        let args = CliArgs::try_parse_from(["server", "--embedder", "dummy"]).expect("valid flags");
+        config.apply(&args);
+        assert!(config.embedder.build().expect("dummy embedder").is_none());
+    }
+
+    #[test]
+    fn invalid_values_are_reported_together() {
+        let mut config = Config::default();
+        config.engine.dim = 0;
+        config.engine.hnsw_m = 1000;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("engine.dim must be greater than 0"), "{err}");
+        assert!(err.contains("engine.hnsw_m must be at most 256"), "{err}");
+
+        let mut config = Config::default();
+        config.server.rebuild_below_recall = Some(1.5);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(
+            err.contains("server.rebuild_below_recall must be between 0 and 1"),
+            "{err}"
+        );
+
+        let mut config = Config::default();
+        config.embedder.kind = EmbedderKind::Http;
+        config.embedder.model = Some("text-embedding-3-small".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(
+            err.contains("embedder.url is required for the http embedder"),
+            "{err}"
+        );
+
+        assert!(toml::from_str::<Config>("[engine]\nmetric = \"manhattan\"").is_err());
+        assert!(toml::from_str::<Config>("[embedder]\nkind = \"openai\"").is_err());
+        assert!(toml::from_str::<Config>("[engine]\nef = 3").is_err());
+    }
+}
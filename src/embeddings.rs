@@ -1,12 +1,36 @@
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+mod http;
+#[cfg(feature = "onnx")]
+mod onnx;
+
+pub use http::{HttpEmbedder, HttpEmbedderConfig};
+#[cfg(feature = "onnx")]
+pub use onnx::{OnnxEmbedder, OnnxEmbedderConfig};
+
 #[derive(Debug, Error)]
 pub enum EmbeddingError {
     #[error("embedding backend not configured")]
     NotConfigured,
 
+    #[error("embedder produces {got}-dimensional vectors, engine expects {expected}")]
+    DimMismatch { expected: usize, got: usize },
+
+    #[error("embedding request timed out")]
+    Timeout,
+
+    #[error("embedding request failed: {0}")]
+    Transport(String),
+
+    #[error("embedding service returned HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("invalid embedding response: {0}")]
+    InvalidResponse(String),
+
     #[error("other error: {0}")]
     Other(String),
 }
@@ -14,11 +38,17 @@ pub enum EmbeddingError {
 /// Simple abstraction over "something that can turn text into vectors".
 pub trait Embedder: Send + Sync {
     fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+
+    /// Output dimension, if known up front. The engine checks it against its
+    /// own `dim` when it is opened.
+    fn dim(&self) -> Option<usize> {
+        None
+    }
 }
 
-/// Placeholder implementation that you can later replace with:
-/// - ONNX Runtime via `ort`
-/// - external embedding API (OpenAI, etc.)
+/// Placeholder implementation that returns zero vectors. Real backends:
+/// - `OnnxEmbedder` (ONNX Runtime via `ort`, behind the `onnx` feature)
+/// - `HttpEmbedder` (OpenAI-compatible `/v1/embeddings` APIs)
 pub struct DummyEmbedder {
     pub dim: usize,
 }
@@ -26,13 +56,85 @@ pub struct DummyEmbedder {
 impl Embedder for DummyEmbedder {
     fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
         // For now, just return zero vectors with the right dimension.
-        Ok(texts
-            .iter()
-            .map(|_| vec![0.0_f32; self.dim])
-            .collect())
+        Ok(texts.iter().map(|_| vec![0.0_f32; self.dim]).collect())
+    }
+
+    fn dim(&self) -> Option<usize> {
+        Some(self.dim)
     }
 }
 
 pub type SharedEmbedder = Arc<dyn Embedder>;
 
+/// How per-token hidden states are reduced to one sentence vector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pooling {
+    /// Average of the token vectors covered by the attention mask.
+    #[default]
+    Mean,
+    /// The first token's vector (`[CLS]` for BERT-style models).
+    Cls,
+}
+
+impl Pooling {
+    /// Pool one sequence of `hidden` (`mask.len()` rows of `dim` floats).
+    pub fn apply(self, hidden: &[f32], mask: &[i64], dim: usize) -> Vec<f32> {
+        match self {
+            Pooling::Cls => hidden[..dim].to_vec(),
+            Pooling::Mean => {
+                let mut sum = vec![0.0_f32; dim];
+                let mut count = 0.0_f32;
+                for (token, &m) in hidden.chunks(dim).zip(mask) {
+                    if m == 0 {
+                        continue;
+                    }
+                    for (s, v) in sum.iter_mut().zip(token) {
+                        *s += v;
+                    }
+                    count += 1.0;
+                }
+                if count > 0.0 {
+                    sum.iter_mut().for_each(|s| *s /= count);
+                }
+                sum
+            }
+        }
+    }
+}
 
+/// Scale `v` to unit length in place; zero vectors are left untouched.
+pub fn l2_normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_pooling_ignores_masked_tokens() {
+        let hidden = [1.0, 2.0, 3.0, 4.0, 100.0, 100.0];
+        assert_eq!(Pooling::Mean.apply(&hidden, &[1, 1, 0], 2), vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn cls_pooling_takes_the_first_token() {
+        let hidden = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(Pooling::Cls.apply(&hidden, &[1, 1], 2), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn l2_normalize_produces_unit_vectors() {
+        let mut v = [3.0, 4.0];
+        l2_normalize(&mut v);
+        assert_eq!(v, [0.6, 0.8]);
+
+        let mut zero = [0.0, 0.0];
+        l2_normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+}
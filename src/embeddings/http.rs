@@ -0,0 +1,222 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Embedder, EmbeddingError};
+
+/// Settings for `HttpEmbedder`.
+#[derive(Debug, Clone)]
+pub struct HttpEmbedderConfig {
+    /// Server root, e.g. `http://localhost:8080` or `https://api.openai.com/v1`.
+    pub base_url: String,
+    pub model: String,
+    /// Sent as `Authorization: Bearer <key>` when set.
+    pub api_key: Option<String>,
+    /// Maximum number of texts per request.
+    pub batch_size: usize,
+    /// Overall deadline for a single request, including reading the response.
+    pub timeout: Duration,
+    /// Extra attempts after a timeout, connection failure, 429 or 5xx.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further attempt.
+    pub initial_backoff: Duration,
+}
+
+impl HttpEmbedderConfig {
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            model: model.into(),
+            api_key: None,
+            batch_size: 64,
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Client for an OpenAI-compatible `POST /v1/embeddings` endpoint.
+pub struct HttpEmbedder {
+    agent: ureq::Agent,
+    url: String,
+    cfg: HttpEmbedderConfig,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl HttpEmbedder {
+    pub fn new(cfg: HttpEmbedderConfig) -> Result<Self, EmbeddingError> {
+        if cfg.batch_size == 0 {
+            return Err(EmbeddingError::Other(
+                "batch_size must be at least 1".to_string(),
+            ));
+        }
+
+        let base = cfg.base_url.trim_end_matches('/');
+        let url = if base.ends_with("/v1") {
+            format!("{base}/embeddings")
+        } else {
+            format!("{base}/v1/embeddings")
+        };
+        let agent = ureq::AgentBuilder::new().timeout(cfg.timeout).build();
+        Ok(Self { agent, url, cfg })
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let body = serde_json::to_string(&EmbeddingRequest {
+            model: &self.cfg.model,
+            input: texts,
+        })
+        .map_err(|e| EmbeddingError::Other(e.to_string()))?;
+
+        let mut backoff = self.cfg.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            match self.send(&body) {
+                Err(e) if attempt < self.cfg.max_retries && is_retryable(&e) => {
+                    tracing::warn!(attempt, error = %e, "embedding request failed, retrying");
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+                Ok(text) => return parse_response(&text, texts.len()),
+            }
+        }
+    }
+
+    fn send(&self, body: &str) -> Result<String, EmbeddingError> {
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json");
+        if let Some(key) = &self.cfg.api_key {
+            request = request.set("Authorization", &format!("Bearer {key}"));
+        }
+
+        match request.send_string(body) {
+            Ok(response) => response.into_string().map_err(io_error),
+            Err(ureq::Error::Status(status, response)) => Err(EmbeddingError::Status {
+                status,
+                body: response.into_string().unwrap_or_default(),
+            }),
+            Err(ureq::Error::Transport(t)) => {
+                let timed_out = std::error::Error::source(&t)
+                    .and_then(|e| e.downcast_ref::<io::Error>())
+                    .is_some_and(is_timeout);
+                if timed_out {
+                    Err(EmbeddingError::Timeout)
+                } else {
+                    Err(EmbeddingError::Transport(t.to_string()))
+                }
+            }
+        }
+    }
+}
+
+impl Embedder for HttpEmbedder {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.cfg.batch_size) {
+            vectors.extend(self.embed_batch(batch)?);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Order embeddings by their `index` and check there is exactly one per input.
+fn parse_response(text: &str, expected: usize) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    let response: EmbeddingResponse =
+        serde_json::from_str(text).map_err(|e| EmbeddingError::InvalidResponse(e.to_string()))?;
+
+    let mut vectors = vec![None; expected];
+    for item in response.data {
+        match vectors.get_mut(item.index) {
+            Some(slot @ None) => *slot = Some(item.embedding),
+            _ => {
+                return Err(EmbeddingError::InvalidResponse(format!(
+                    "unexpected or duplicate index {}",
+                    item.index
+                )))
+            }
+        }
+    }
+    vectors
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| {
+            v.ok_or_else(|| EmbeddingError::InvalidResponse(format!("missing embedding {i}")))
+        })
+        .collect()
+}
+
+fn is_retryable(err: &EmbeddingError) -> bool {
+    match err {
+        EmbeddingError::Timeout | EmbeddingError::Transport(_) => true,
+        EmbeddingError::Status { status, .. } => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+fn io_error(err: io::Error) -> EmbeddingError {
+    if is_timeout(&err) {
+        EmbeddingError::Timeout
+    } else {
+        EmbeddingError::Transport(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_response_orders_by_index_and_rejects_gaps() {
+        let ok =
+            r#"{"data": [{"index": 1, "embedding": [2.0]}, {"index": 0, "embedding": [1.0]}]}"#;
+        assert_eq!(parse_response(ok, 2).unwrap(), vec![vec![1.0], vec![2.0]]);
+
+        let missing = r#"{"data": [{"index": 0, "embedding": [1.0]}]}"#;
+        assert!(matches!(
+            parse_response(missing, 2),
+            Err(EmbeddingError::InvalidResponse(_))
+        ));
+
+        let duplicate =
+            r#"{"data": [{"index": 0, "embedding": [1.0]}, {"index": 0, "embedding": [1.0]}]}"#;
+        assert!(matches!(
+            parse_response(duplicate, 2),
+            Err(EmbeddingError::InvalidResponse(_))
+        ));
+
+        assert!(matches!(
+            parse_response("not json", 1),
+            Err(EmbeddingError::InvalidResponse(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ort::session::{Session, SessionInputValue};
+use ort::value::Tensor;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::{l2_normalize, Embedder, EmbeddingError, Pooling};
+
+/// Settings for `OnnxEmbedder`.
+#[derive(Debug, Clone)]
+pub struct OnnxEmbedderConfig {
+    /// Directory holding `tokenizer.json` and `model.onnx` (or `onnx/model.onnx`,
+    /// the layout of sentence-transformers exports).
+    pub model_dir: PathBuf,
+    pub pooling: Pooling,
+    /// Scale every embedding to unit length.
+    pub normalize: bool,
+    /// Maximum number of texts per model invocation.
+    pub batch_size: usize,
+    /// Inputs are truncated to this many tokens.
+    pub max_length: usize,
+}
+
+impl OnnxEmbedderConfig {
+    pub fn new(model_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            model_dir: model_dir.into(),
+            pooling: Pooling::Mean,
+            normalize: true,
+            batch_size: 32,
+            max_length: 512,
+        }
+    }
+}
+
+/// Sentence-transformer style embedder running a local ONNX model.
+///
+/// The model must take `input_ids` and `attention_mask` (and optionally
+/// `token_type_ids`) and return either per-token hidden states
+/// `[batch, seq, dim]`, which are pooled, or sentence vectors `[batch, dim]`.
+pub struct OnnxEmbedder {
+    // `Session::run` needs `&mut self`; `Embedder::embed` only gets `&self`.
+    session: Mutex<Session>,
+    tokenizer: Tokenizer,
+    input_names: Vec<String>,
+    output_name: String,
+    cfg: OnnxEmbedderConfig,
+    dim: usize,
+}
+
+impl OnnxEmbedder {
+    pub fn new(cfg: OnnxEmbedderConfig) -> Result<Self, EmbeddingError> {
+        if cfg.batch_size == 0 {
+            return Err(EmbeddingError::Other(
+                "batch_size must be at least 1".to_string(),
+            ));
+        }
+
+        let mut tokenizer = Tokenizer::from_file(cfg.model_dir.join("tokenizer.json"))
+            .map_err(|e| EmbeddingError::Other(format!("loading tokenizer: {e}")))?;
+        if tokenizer.get_padding().is_none() {
+            tokenizer.with_padding(Some(PaddingParams::default()));
+        }
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: cfg.max_length,
+                ..Default::default()
+            }))
+            .map_err(|e| EmbeddingError::Other(format!("configuring tokenizer: {e}")))?;
+
+        let session = Session::builder()
+            .and_then(|b| b.commit_from_file(model_path(&cfg.model_dir)))
+            .map_err(|e| EmbeddingError::Other(format!("loading model: {e}")))?;
+
+        let input_names: Vec<String> = session.inputs.iter().map(|i| i.name.clone()).collect();
+        for name in &input_names {
+            if !matches!(
+                name.as_str(),
+                "input_ids" | "attention_mask" | "token_type_ids"
+            ) {
+                return Err(EmbeddingError::Other(format!(
+                    "unsupported model input: {name}"
+                )));
+            }
+        }
+        let output_name = session
+            .outputs
+            .iter()
+            .find(|o| o.name == "last_hidden_state")
+            .or_else(|| session.outputs.first())
+            .map(|o| o.name.clone())
+            .ok_or_else(|| EmbeddingError::Other("model has no outputs".to_string()))?;
+
+        let mut embedder = Self {
+            session: Mutex::new(session),
+            tokenizer,
+            input_names,
+            output_name,
+            cfg,
+            dim: 0,
+        };
+
+        // Exported models usually leave some output dims dynamic, so run a
+        // probe instead of trusting the declared shape.
+        let probe = embedder.embed_batch(&["dimension probe".to_string()])?;
+        embedder.dim = probe.first().map_or(0, Vec::len);
+        Ok(embedder)
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| EmbeddingError::Other(format!("tokenizing: {e}")))?;
+
+        let batch = encodings.len();
+        let seq = encodings.first().map_or(0, |e| e.len());
+        let mut ids = Vec::with_capacity(batch * seq);
+        let mut mask = Vec::with_capacity(batch * seq);
+        let mut type_ids = Vec::with_capacity(batch * seq);
+        for encoding in &encodings {
+            ids.extend(encoding.get_ids().iter().map(|&x| x as i64));
+            mask.extend(encoding.get_attention_mask().iter().map(|&x| x as i64));
+            type_ids.extend(encoding.get_type_ids().iter().map(|&x| x as i64));
+        }
+
+        let shape = vec![batch as i64, seq as i64];
+        let mut inputs: Vec<(String, SessionInputValue)> =
+            Vec::with_capacity(self.input_names.len());
+        for name in &self.input_names {
+            let data = match name.as_str() {
+                "input_ids" => ids.clone(),
+                "attention_mask" => mask.clone(),
+                _ => type_ids.clone(),
+            };
+            let tensor = Tensor::from_array((shape.clone(), data))
+                .map_err(|e| EmbeddingError::Other(format!("building input {name}: {e}")))?;
+            inputs.push((name.clone(), tensor.into()));
+        }
+
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let outputs = session
+            .run(inputs)
+            .map_err(|e| EmbeddingError::Other(format!("running model: {e}")))?;
+        let (out_shape, hidden) = outputs[self.output_name.as_str()]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| EmbeddingError::Other(format!("reading model output: {e}")))?;
+
+        let mut vectors: Vec<Vec<f32>> = match **out_shape {
+            [_, s, d] if s > 0 && d > 0 => {
+                let (s, d) = (s as usize, d as usize);
+                hidden
+                    .chunks(s * d)
+                    .zip(mask.chunks(seq.max(1)))
+                    .map(|(h, m)| self.cfg.pooling.apply(h, m, d))
+                    .collect()
+            }
+            [_, d] if d > 0 => hidden.chunks(d as usize).map(<[f32]>::to_vec).collect(),
+            ref other => {
+                return Err(EmbeddingError::Other(format!(
+                    "unexpected model output shape {other:?}"
+                )))
+            }
+        };
+
+        if self.cfg.normalize {
+            vectors.iter_mut().for_each(|v| l2_normalize(v));
+        }
+        Ok(vectors)
+    }
+}
+
+impl Embedder for OnnxEmbedder {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.cfg.batch_size) {
+            vectors.extend(self.embed_batch(batch)?);
+        }
+        Ok(vectors)
+    }
+
+    fn dim(&self) -> Option<usize> {
+        Some(self.dim)
+    }
+}
+
+fn model_path(dir: &Path) -> PathBuf {
+    let nested = dir.join("onnx").join("model.onnx");
+    if nested.exists() {
+        nested
+    } else {
+        dir.join("model.onnx")
+    }
+}
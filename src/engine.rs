@@ -1,22 +1,82 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use serde::Serialize;
+use serde_json::Value;
 
-use crate::embeddings::SharedEmbedder;
-use crate::health::{basic_index_health, HealthReport};
-use crate::index::{HnswIndex, IndexConfig, IndexError};
-use crate::storage::{SqliteVectorStore, StorageConfig, StorageError};
+use crate::backup::{self, BackupError, BackupManifest};
+use crate::embeddings::{EmbeddingError, SharedEmbedder};
+use crate::export::{self, ExportError, ExportFormat, ExportReport, ExportSnapshot};
+#[cfg(any(test, feature = "fault-injection"))]
+use crate::faults::SharedFaults;
+use crate::filter::Filter;
+use crate::health::{
+    basic_index_health, ConsistencyReport, HealthReport, QuarantineRestoreReport, RecallReport,
+    RepairAction, ScrubReport,
+};
+use crate::import::{self, ImportError, ImportOptions, ImportRecord, ImportReport};
+use crate::index::{IndexConfig, IndexError, Metric, MAX_FILTER_CANDIDATES};
+use crate::rebuild::{
+    effective_config, record_params, DeltaOp, RebuildJob, RebuildParams, RebuildState,
+    RebuildStatus,
+};
+use crate::segments::{CompactionJob, SegmentedIndex};
+use crate::snapshot::{self, SnapshotError, SnapshotInfo};
+use crate::storage::{Op, SqliteVectorStore, StorageConfig, StorageError, StoredRow};
 
 #[derive(Debug, Clone)]
 pub struct EngineConfig {
     pub dim: usize,
+    /// Distance metric; persisted with the data and checked on every open.
+    pub metric: Metric,
     pub storage_path: PathBuf,
+    /// Slots per sealed index segment.
     pub hnsw_max_elements: usize,
+    /// Slots the mutable segment takes new writes into before it is sealed;
+    /// compaction later merges sealed segments up to `hnsw_max_elements`.
+    pub mutable_segment_capacity: usize,
     pub hnsw_m: usize,
     pub hnsw_ef_construction: usize,
     pub hnsw_ef_search: usize,
 }
 
+impl EngineConfig {
+    fn index_config(&self) -> IndexConfig {
+        IndexConfig {
+            dim: self.dim,
+            metric: self.metric,
+            max_elements: self.hnsw_max_elements,
+            mutable_segment_capacity: self.mutable_segment_capacity,
+            m: self.hnsw_m,
+            ef_construction: self.hnsw_ef_construction,
+            ef_search: self.hnsw_ef_search,
+        }
+    }
+
+    /// Why no engine can be opened with this configuration; see
+    /// `IndexConfig::problems`.
+    pub fn problems(&self) -> Vec<String> {
+        self.index_config().problems()
+    }
+
+    /// Reject a configuration with any `problems`.
+    pub fn validate(&self) -> Result<(), EngineError> {
+        reject(self.problems())
+    }
+}
+
+/// `InvalidParameter` listing `problems`, if there are any.
+pub(crate) fn reject(problems: Vec<String>) -> Result<(), EngineError> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(EngineError::InvalidParameter(problems.join("; ")))
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum EngineError {
     #[error("index error: {0}")]
@@ -24,72 +84,1242 @@ pub enum EngineError {
 
     #[error("storage error: {0}")]
     Storage(#[from] StorageError),
+
+    #[error("embedding error: {0}")]
+    Embedding(#[from] EmbeddingError),
+
+    #[error("snapshot error: {0}")]
+    Snapshot(#[from] SnapshotError),
+
+    #[error("backup error: {0}")]
+    Backup(#[from] BackupError),
+
+    #[error("import error: {0}")]
+    Import(#[from] ImportError),
+
+    #[error("export error: {0}")]
+    Export(#[from] ExportError),
+
+    #[error("expected {expected} {field} for the given ids, got {got}")]
+    LengthMismatch {
+        field: &'static str,
+        expected: usize,
+        got: usize,
+    },
+
+    #[error("{key} mismatch: stored data uses {stored}, configured {configured}")]
+    ConfigMismatch {
+        key: &'static str,
+        stored: String,
+        configured: String,
+    },
+
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+
+    #[error("an index rebuild is already running")]
+    RebuildInProgress,
+
+    #[error("index rebuild failed: {0}")]
+    RebuildFailed(String),
 }
 
+/// Vectors compared by each `check_consistency` call; successive calls walk
+/// through all stored ids.
+const CHECK_SAMPLE: usize = 256;
+
+/// Stored vectors read at a time by the exact scan of `estimate_recall`.
+const RECALL_PAGE: usize = 1_000;
+
+/// `repair` rebuilds the whole index once more than 1/`REBUILD_FRACTION` of
+/// the stored vectors need fixing.
+const REBUILD_FRACTION: usize = 4;
+
 pub struct SelfHealingVectorDb {
     dim: usize,
-    index: HnswIndex,
+    /// Index settings from `EngineConfig`; `index_cfg` differs once a
+    /// background rebuild changes them.
+    configured: IndexConfig,
+    index_cfg: IndexConfig,
+    index: SegmentedIndex,
+    storage_cfg: StorageConfig,
     store: SqliteVectorStore,
-    _embedder: Option<SharedEmbedder>,
+    embedder: Option<SharedEmbedder>,
+    snapshot_dir: PathBuf,
+    /// Write sequence number covered by the snapshot on disk, if any.
+    last_snapshot: Mutex<Option<u64>>,
+    last_check: Mutex<Option<ConsistencyReport>>,
+    /// Where the next sampled consistency check starts in the stored ids.
+    check_cursor: AtomicUsize,
+    last_recall: Mutex<Option<RecallReport>>,
+    /// Offset of the next recall sample in the stored ids.
+    recall_cursor: AtomicUsize,
+    rebuild: Option<RebuildJob>,
+    /// Outcome of the last finished background rebuild.
+    last_rebuild: RebuildStatus,
+    compaction: Option<CompactionJob>,
+    #[cfg(any(test, feature = "fault-injection"))]
+    faults: Option<SharedFaults>,
+}
+
+/// Ids on which storage and the index disagree.
+struct Discrepancies {
+    stored: usize,
+    indexed: usize,
+    missing: Vec<i64>,
+    extra: Vec<i64>,
+    stale: Vec<i64>,
+    compared: usize,
+}
+
+impl Discrepancies {
+    fn count(&self) -> usize {
+        self.missing.len() + self.extra.len() + self.stale.len()
+    }
+
+    fn report(&self, repair: Option<RepairAction>) -> ConsistencyReport {
+        ConsistencyReport {
+            stored: self.stored,
+            indexed: self.indexed,
+            missing: self.missing.len(),
+            extra: self.extra.len(),
+            stale: self.stale.len(),
+            compared: self.compared,
+            examples: [&self.missing, &self.extra, &self.stale]
+                .into_iter()
+                .flatten()
+                .copied()
+                .take(10)
+                .collect(),
+            repair,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct SearchResult {
     pub id: usize,
     pub distance: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Optional knobs for `SelfHealingVectorDb::search_with`.
+#[derive(Debug, Clone, Default)]
+pub struct SearchParams {
+    /// Attach each result's stored JSON payload.
+    pub with_payload: bool,
+    /// Attach the source text for vectors ingested via `add_texts`.
+    pub with_text: bool,
+    /// Only return vectors whose payload matches this predicate. Storage
+    /// finds the matching ids; up to `MAX_FILTER_CANDIDATES` of them are
+    /// scored exactly, and more restrict the index search as a predicate on
+    /// its candidates.
+    pub filter: Option<Filter>,
+}
+
+/// The engine's embedder and dimension, detached from the engine so callers
+/// can embed texts without holding it, e.g. outside a lock around it.
+#[derive(Clone)]
+pub struct TextEmbedder {
+    embedder: Option<SharedEmbedder>,
+    dim: usize,
+}
+
+impl TextEmbedder {
+    /// Run the embedder and flatten its output, checking every vector has `dim` components.
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<f32>, EngineError> {
+        let embedder = self
+            .embedder
+            .as_ref()
+            .ok_or(EmbeddingError::NotConfigured)?;
+
+        let mut flat = Vec::with_capacity(texts.len() * self.dim);
+        for vector in embedder.embed(texts)? {
+            if vector.len() != self.dim {
+                return Err(IndexError::DimMismatch {
+                    expected: self.dim,
+                    got: vector.len(),
+                }
+                .into());
+            }
+            flat.extend_from_slice(&vector);
+        }
+        Ok(flat)
+    }
+}
+
+/// Settings for `SelfHealingVectorDb::estimate_recall`.
+#[derive(Debug, Clone)]
+pub struct RecallParams {
+    /// Neighbours compared per query.
+    pub k: usize,
+    /// Stored vectors used as sample queries.
+    pub queries: usize,
+    /// Recall below this is reported as degraded.
+    pub min_recall: f32,
+}
+
+impl Default for RecallParams {
+    fn default() -> Self {
+        Self {
+            k: 10,
+            queries: 32,
+            min_recall: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteKind {
+    Inserted,
+    Updated,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AddResult {
+    pub id: i64,
+    pub kind: WriteKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoredVector {
+    pub id: i64,
+    pub vector: Vec<f32>,
+    pub payload: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
 }
 
 impl SelfHealingVectorDb {
     pub fn new(cfg: EngineConfig, embedder: Option<SharedEmbedder>) -> Result<Self, EngineError> {
-        let store = SqliteVectorStore::new(&StorageConfig {
+        cfg.validate()?;
+        if let Some(got) = embedder.as_ref().and_then(|e| e.dim()) {
+            if got != cfg.dim {
+                return Err(EmbeddingError::DimMismatch {
+                    expected: cfg.dim,
+                    got,
+                }
+                .into());
+            }
+        }
+
+        let storage_cfg = StorageConfig {
             path: cfg.storage_path.clone(),
             dim: cfg.dim,
-        })?;
+        };
+        let store = SqliteVectorStore::new(&storage_cfg)?;
 
-        let mut index = HnswIndex::new(&IndexConfig {
-            dim: cfg.dim,
-            max_elements: cfg.hnsw_max_elements,
-            m: cfg.hnsw_m,
-            ef_construction: cfg.hnsw_ef_construction,
-            ef_search: cfg.hnsw_ef_search,
-        })?;
+        // Refuse to reinterpret existing data under a different metric.
+        // Stores written before the metric was recorded were always L2.
+        let stored_metric = match store.meta("metric")? {
+            Some(metric) => Some(metric),
+            None if store.count()? > 0 => Some(Metric::L2.to_string()),
+            None => None,
+        };
+        match stored_metric {
+            Some(stored) if stored != cfg.metric.as_str() => {
+                return Err(EngineError::ConfigMismatch {
+                    key: "metric",
+                    stored,
+                    configured: cfg.metric.to_string(),
+                });
+            }
+            _ => store.set_meta("metric", cfg.metric.as_str())?,
+        }
 
-        // Bootstrap from storage (self-healing on startup)
-        let (ids, vecs) = store.load_all()?;
-        for (i, chunk) in vecs.chunks(cfg.dim).enumerate() {
-            let id = ids[i] as usize;
-            index.insert(id, chunk.to_vec())?;
+        let configured = cfg.index_config();
+        let index_cfg = effective_config(&store, &configured)?;
+
+        // Prefer the saved graph plus the writes made since it was taken; the
+        // full rebuild from storage stays the fallback (self-healing on startup).
+        let snapshot_dir = snapshot::snapshot_dir(&cfg.storage_path);
+        let (index, last_snapshot) = match restore(&snapshot_dir, &index_cfg, &store) {
+            Ok(Some((index, seq))) => (index, Some(seq)),
+            Ok(None) => (rebuild(&index_cfg, &store)?, None),
+            Err(e) => {
+                tracing::warn!(
+                    "discarding index snapshot in {}: {e}; rebuilding from storage",
+                    snapshot_dir.display()
+                );
+                // Its segment files must not be mistaken for the new index's.
+                snapshot::remove(&snapshot_dir).map_err(SnapshotError::Io)?;
+                (rebuild(&index_cfg, &store)?, None)
+            }
+        };
+        if last_snapshot.is_none() {
+            // Without a snapshot nothing will replay the logged writes.
+            store.truncate_log(store.write_seq()?)?;
         }
 
         Ok(Self {
             dim: cfg.dim,
+            configured,
+            index_cfg,
             index,
+            storage_cfg,
             store,
-            _embedder: embedder,
+            embedder,
+            snapshot_dir,
+            last_snapshot: Mutex::new(last_snapshot),
+            last_check: Mutex::new(None),
+            check_cursor: AtomicUsize::new(0),
+            last_recall: Mutex::new(None),
+            recall_cursor: AtomicUsize::new(0),
+            rebuild: None,
+            last_rebuild: RebuildStatus::idle(),
+            compaction: None,
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
         })
     }
 
-    pub fn add_vectors(&mut self, ids: &[i64], vectors: &[f32]) -> Result<(), EngineError> {
-        self.store.add(ids, vectors)?;
+    /// Save the index graph next to the SQLite file so the next start can
+    /// load it instead of rebuilding.
+    ///
+    /// Returns `None` without writing anything if nothing changed since the
+    /// last snapshot or the index has never held a vector.
+    pub fn snapshot(&self) -> Result<Option<SnapshotInfo>, EngineError> {
+        let seq = self.store.write_seq()?;
+        let mut last = self.last_snapshot.lock().unwrap();
+        if *last == Some(seq) || self.index.slot_count() == 0 {
+            return Ok(None);
+        }
+
+        let info = snapshot::save(&self.snapshot_dir, &self.index, seq)?;
+        *last = Some(seq);
+        self.store.truncate_log(seq)?;
+        Ok(Some(info))
+    }
+
+    /// Write a backup bundle of storage and the index to the new or empty
+    /// directory `dest`, without blocking other readers.
+    pub fn backup(&self, dest: &Path) -> Result<BackupManifest, EngineError> {
+        Ok(backup::create(dest, &self.store, &self.index)?)
+    }
+
+    /// Replace storage and the index with the backup bundle in `src`.
+    ///
+    /// The bundle is validated in full first; if it is incomplete, damaged
+    /// or for a different dim or metric, nothing changes. Writes made since
+    /// the backup are lost.
+    pub fn restore_backup(&mut self, src: &Path) -> Result<BackupManifest, EngineError> {
+        if self.rebuild.is_some() {
+            return Err(EngineError::RebuildInProgress);
+        }
+        let (manifest, mut index) = backup::validate(src, &self.index_cfg)?;
+
+        self.store.restore_from(&backup::database_path(src))?;
+        index.renumber_after(&self.index);
+        #[cfg(any(test, feature = "fault-injection"))]
+        index.set_faults(self.faults.clone());
+        self.index = index;
+        self.compaction = None;
+        *self.last_check.get_mut().unwrap() = None;
+        *self.last_recall.get_mut().unwrap() = None;
+
+        // The old snapshot may cover writes numbered like the backup's but
+        // made after it; replace it with one of the restored index.
+        snapshot::remove(&self.snapshot_dir).map_err(SnapshotError::Io)?;
+        *self.last_snapshot.get_mut().unwrap() = None;
+        self.snapshot()?;
+        tracing::info!(
+            "restored backup of write {} from {}",
+            manifest.seq,
+            src.display()
+        );
+        Ok(manifest)
+    }
+
+    /// Bulk-load the vectors in the file at `path`; see `import`.
+    pub fn import_file(
+        &mut self,
+        path: &Path,
+        options: &ImportOptions,
+    ) -> Result<ImportReport, EngineError> {
+        let records = import::read_file(path, options.format, options.first_id)?;
+        self.import(records, options.batch_size)
+    }
+
+    /// Bulk-load `records`, replacing stored vectors with the same ids.
+    ///
+    /// Records go to storage in transactions of `batch_size`, and the index
+    /// is rebuilt once at the end rather than updated per vector. If a
+    /// record is invalid, the batches before it stay imported and the error
+    /// is returned; importing the same input again is harmless.
+    pub fn import(
+        &mut self,
+        records: impl IntoIterator<Item = Result<ImportRecord, ImportError>>,
+        batch_size: usize,
+    ) -> Result<ImportReport, EngineError> {
+        if self.rebuild.is_some() {
+            return Err(EngineError::RebuildInProgress);
+        }
+        let mut report = ImportReport::default();
+        let result = self.import_batches(records.into_iter(), batch_size.max(1), &mut report);
+        if report.imported > 0 {
+            self.rebuild_index()?;
+        }
+        tracing::info!(
+            "imported {} vectors in {} batches",
+            report.imported,
+            report.batches
+        );
+        result.map(|()| report)
+    }
+
+    fn import_batches(
+        &mut self,
+        records: impl Iterator<Item = Result<ImportRecord, ImportError>>,
+        batch_size: usize,
+        report: &mut ImportReport,
+    ) -> Result<(), EngineError> {
+        let mut ids = Vec::with_capacity(batch_size);
+        let mut vectors = Vec::with_capacity(batch_size * self.dim);
+        let mut payloads = Vec::with_capacity(batch_size);
+        let mut records = records.enumerate().peekable();
+        while let Some((i, record)) = records.next() {
+            let record = record?;
+            if record.vector.len() != self.dim {
+                return Err(ImportError::Record {
+                    record: i,
+                    message: format!(
+                        "expected {} components, got {}",
+                        self.dim,
+                        record.vector.len()
+                    ),
+                }
+                .into());
+            }
+            ids.push(record.id);
+            vectors.extend(record.vector);
+            payloads.push(record.payload);
+
+            if ids.len() == batch_size || records.peek().is_none() {
+                self.store.add_with_payloads(&ids, &vectors, &payloads)?;
+                report.imported += ids.len();
+                report.batches += 1;
+                ids.clear();
+                vectors.clear();
+                payloads.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Stream every stored vector to `out` as `format`, without loading
+    /// them all into memory.
+    pub fn export<W: std::io::Write>(
+        &self,
+        format: ExportFormat,
+        out: W,
+    ) -> Result<ExportReport, EngineError> {
+        Ok(export::write(&self.store, format, out)?)
+    }
+
+    /// A consistent copy of storage to export from after the engine is
+    /// released, so writes only wait for the copy, not the whole export.
+    pub fn export_snapshot(&self) -> Result<ExportSnapshot, EngineError> {
+        Ok(ExportSnapshot::take(&self.store, &self.storage_cfg)?)
+    }
+
+    /// Export to a new file at `path`, in `format` or the one its
+    /// extension names.
+    pub fn export_file(
+        &self,
+        path: &Path,
+        format: Option<ExportFormat>,
+    ) -> Result<ExportReport, EngineError> {
+        let format = match format {
+            Some(format) => format,
+            None => ExportFormat::from_path(path)?,
+        };
+        let file = std::fs::File::create(path).map_err(ExportError::Io)?;
+        self.export(format, std::io::BufWriter::new(file))
+    }
+
+    /// Insert or update vectors by id.
+    ///
+    /// Re-adding an existing id replaces its vector in both storage and the
+    /// index. Returns one `AddResult` per id, in input order.
+    pub fn add_vectors(
+        &mut self,
+        ids: &[i64],
+        vectors: &[f32],
+    ) -> Result<Vec<AddResult>, EngineError> {
+        self.add_vectors_with_payloads(ids, vectors, &[])
+    }
+
+    /// Like `add_vectors`, but also stores a JSON payload per id.
+    ///
+    /// `payloads` is either empty or holds one value per id (`null` for none).
+    pub fn add_vectors_with_payloads(
+        &mut self,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+    ) -> Result<Vec<AddResult>, EngineError> {
+        self.insert(ids, vectors, payloads, &[])
+    }
+
+    fn insert(
+        &mut self,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+        texts: &[String],
+    ) -> Result<Vec<AddResult>, EngineError> {
+        self.validate_batch(ids, vectors, payloads, texts)?;
+        let replaced = self.store.add_with_texts(ids, vectors, payloads, texts)?;
+
+        if let Some(job) = &mut self.rebuild {
+            for (i, chunk) in vectors.chunks(self.dim).enumerate() {
+                job.delta
+                    .push(DeltaOp::Upsert(ids[i] as usize, chunk.to_vec()));
+            }
+        }
+        let index = &mut self.index;
+        let applied = catch_unwind(AssertUnwindSafe(|| {
+            for (i, chunk) in vectors.chunks(self.dim).enumerate() {
+                index.insert(ids[i] as usize, chunk.to_vec())?;
+            }
+            Ok::<_, IndexError>(())
+        }));
+        // The batch is committed either way, so the write succeeded. The index
+        // may hold part of it (and, after a panic, poisoned locks); storage
+        // has everything to start over.
+        match applied {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!("index insert failed: {e}; rebuilding the index from storage");
+                self.rebuild_index()?;
+            }
+            Err(_) => {
+                tracing::error!("index insert panicked; rebuilding the index from storage");
+                self.rebuild_index()?;
+            }
+        }
+        self.maintain();
+
+        Ok(ids
+            .iter()
+            .zip(replaced)
+            .map(|(&id, replaced)| AddResult {
+                id,
+                kind: if replaced {
+                    WriteKind::Updated
+                } else {
+                    WriteKind::Inserted
+                },
+            })
+            .collect())
+    }
+
+    /// Inject failures into the store and the index (see `Fault`); they also
+    /// apply to indexes that later replace the current one.
+    #[cfg(any(test, feature = "fault-injection"))]
+    pub fn set_faults(&mut self, faults: Option<SharedFaults>) {
+        self.store.set_faults(faults.clone());
+        self.index.set_faults(faults.clone());
+        self.faults = faults;
+    }
+
+    /// Swap in finished background rebuilds and compactions, and start
+    /// compacting when sealed segments have become sparse.
+    ///
+    /// Called on every write; the server also calls it periodically so work
+    /// finished between writes is picked up.
+    pub fn maintain(&mut self) {
+        if self.rebuild_ready() {
+            if let Err(e) = self.finish_rebuild() {
+                tracing::warn!("index rebuild failed: {e}");
+            }
+        }
+        if self.compaction_ready() {
+            let job = self
+                .compaction
+                .take()
+                .expect("compaction_ready checked the job");
+            match job.finish(&mut self.index) {
+                // The snapshot on disk still holds the old segments.
+                Ok(true) => *self.last_snapshot.get_mut().unwrap() = None,
+                Ok(false) => {}
+                Err(e) => tracing::warn!("segment compaction failed: {e}"),
+            }
+        }
+        // A rebuild replaces every segment, so there is nothing to compact
+        // until it is done.
+        if self.rebuild.is_some() || self.compaction.is_some() {
+            return;
+        }
+        let plan = self.index.compaction_plan();
+        if !plan.is_empty() {
+            tracing::debug!("compacting {} index segments", plan.len());
+            self.compaction = Some(CompactionJob::start(&self.index, plan));
+        }
+    }
+
+    /// Whether `maintain` has finished background work to swap in or new
+    /// work to start.
+    pub fn needs_maintenance(&self) -> bool {
+        self.rebuild_ready()
+            || self.compaction_ready()
+            || (self.rebuild.is_none()
+                && self.compaction.is_none()
+                && !self.index.compaction_plan().is_empty())
+    }
 
-        for (i, chunk) in vectors.chunks(self.dim).enumerate() {
-            let id = ids[i] as usize;
-            self.index.insert(id, chunk.to_vec())?;
+    pub fn is_compacting(&self) -> bool {
+        self.compaction.is_some()
+    }
+
+    fn compaction_ready(&self) -> bool {
+        self.compaction
+            .as_ref()
+            .is_some_and(|job| job.is_finished())
+    }
+
+    /// Reject malformed batches before anything is written.
+    fn validate_batch(
+        &self,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+        texts: &[String],
+    ) -> Result<(), EngineError> {
+        if vectors.len() != ids.len() * self.dim {
+            // A buffer that splits evenly across the ids has the wrong vector width.
+            let width = vectors.len() / ids.len().max(1);
+            if !ids.is_empty() && width * ids.len() == vectors.len() {
+                return Err(IndexError::DimMismatch {
+                    expected: self.dim,
+                    got: width,
+                }
+                .into());
+            }
+            return Err(EngineError::LengthMismatch {
+                field: "vector components",
+                expected: ids.len() * self.dim,
+                got: vectors.len(),
+            });
+        }
+        for (field, got) in [("payloads", payloads.len()), ("texts", texts.len())] {
+            if got != 0 && got != ids.len() {
+                return Err(EngineError::LengthMismatch {
+                    field,
+                    expected: ids.len(),
+                    got,
+                });
+            }
         }
         Ok(())
     }
 
+    /// Embed `texts` with the configured embedder and store them by id.
+    ///
+    /// The source text is kept next to each vector so searches can return it.
+    pub fn add_texts(
+        &mut self,
+        ids: &[i64],
+        texts: &[String],
+        payloads: &[Value],
+    ) -> Result<Vec<AddResult>, EngineError> {
+        let vectors = self.text_embedder().embed(texts)?;
+        self.add_embedded_texts(ids, texts, &vectors, payloads)
+    }
+
+    /// Store `texts` with the `vectors` a `TextEmbedder` produced for them.
+    pub fn add_embedded_texts(
+        &mut self,
+        ids: &[i64],
+        texts: &[String],
+        vectors: &[f32],
+        payloads: &[Value],
+    ) -> Result<Vec<AddResult>, EngineError> {
+        if texts.len() != ids.len() {
+            return Err(EngineError::LengthMismatch {
+                field: "texts",
+                expected: ids.len(),
+                got: texts.len(),
+            });
+        }
+        self.insert(ids, vectors, payloads, texts)
+    }
+
+    /// Delete vectors by id from storage and the index.
+    ///
+    /// Returns the number of ids that were actually present.
+    pub fn delete_vectors(&mut self, ids: &[i64]) -> Result<usize, EngineError> {
+        let deleted = self.store.delete(ids)?;
+
+        for &id in &deleted {
+            self.index.delete(id as usize);
+            if let Some(job) = &mut self.rebuild {
+                job.delta.push(DeltaOp::Delete(id as usize));
+            }
+        }
+        self.maintain();
+        Ok(deleted.len())
+    }
+
+    /// Fetch stored vectors by id from storage (the source of truth).
+    ///
+    /// Ids that are not stored are omitted from the result.
+    pub fn get(&self, ids: &[i64]) -> Result<Vec<StoredVector>, EngineError> {
+        Ok(self
+            .store
+            .get(ids)?
+            .into_iter()
+            .map(|row| StoredVector {
+                id: row.id,
+                vector: row.vector,
+                payload: row.payload,
+                text: row.text,
+            })
+            .collect())
+    }
+
     pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>, EngineError> {
-        let neighbors = self.index.search(query, k)?;
-        Ok(neighbors
+        self.search_with(query, k, &SearchParams::default())
+    }
+
+    pub fn search_with(
+        &self,
+        query: &[f32],
+        k: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResult>, EngineError> {
+        let neighbors = match &params.filter {
+            Some(filter) => {
+                let matching: Vec<usize> = self
+                    .store
+                    .filter_ids(filter)?
+                    .into_iter()
+                    .map(|id| id as usize)
+                    .collect();
+                if matching.len() <= MAX_FILTER_CANDIDATES {
+                    // Few enough to score exactly, which a graph search
+                    // that skips most of its candidates cannot match.
+                    self.index.exact_search(query, k, &matching)?
+                } else {
+                    let matching: HashSet<usize> = matching.into_iter().collect();
+                    self.index
+                        .search_filtered(query, k, |id| matching.contains(&id))?
+                }
+            }
+            None => self.index.search(query, k)?,
+        };
+
+        let mut results = Vec::with_capacity(neighbors.len());
+        for (id, distance) in neighbors {
+            let payload = if params.with_payload {
+                self.store.payload(id as i64)?
+            } else {
+                None
+            };
+            let text = if params.with_text {
+                self.store.text(id as i64)?
+            } else {
+                None
+            };
+            results.push(SearchResult {
+                id,
+                distance,
+                payload,
+                text,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Embed `query` with the configured embedder and search with it.
+    pub fn search_text(&self, query: &str, k: usize) -> Result<Vec<SearchResult>, EngineError> {
+        self.search_text_with(query, k, &SearchParams::default())
+    }
+
+    pub fn search_text_with(
+        &self,
+        query: &str,
+        k: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResult>, EngineError> {
+        let vector = self.text_embedder().embed(&[query.to_string()])?;
+        self.search_with(&vector, k, params)
+    }
+
+    /// What `add_texts` and `search_text` embed with; it can run without
+    /// borrowing the engine.
+    pub fn text_embedder(&self) -> TextEmbedder {
+        TextEmbedder {
+            embedder: self.embedder.clone(),
+            dim: self.dim,
+        }
+    }
+
+    /// Compare the stored and indexed id sets, and the vectors of a sample
+    /// of ids, without changing anything.
+    ///
+    /// The result is also reported by `health` until the next check.
+    pub fn check_consistency(&self) -> Result<ConsistencyReport, EngineError> {
+        let report = self.find_discrepancies(Some(CHECK_SAMPLE))?.report(None);
+        *self.last_check.lock().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Compare every stored vector with the index, like `repair`, without
+    /// changing anything.
+    ///
+    /// The result is also reported by `health` until the next check.
+    pub fn verify(&self) -> Result<ConsistencyReport, EngineError> {
+        let report = self.find_discrepancies(None)?.report(None);
+        *self.last_check.lock().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Compare storage and the index in full and bring the index back in
+    /// line with storage.
+    ///
+    /// A few bad entries are fixed in place; if many are off, the index is
+    /// rebuilt from storage instead.
+    pub fn repair(&mut self) -> Result<ConsistencyReport, EngineError> {
+        // Corrupt rows cannot be repaired from; set them aside first.
+        self.scrub()?;
+        let found = self.find_discrepancies(None)?;
+        if found.count() == 0 {
+            let report = found.report(None);
+            *self.last_check.get_mut().unwrap() = Some(report.clone());
+            return Ok(report);
+        }
+
+        let action = if found.count() * REBUILD_FRACTION > found.stored {
+            self.rebuild_index()?;
+            RepairAction::Rebuilt
+        } else {
+            for &id in &found.extra {
+                self.index.delete(id as usize);
+            }
+            let reinsert: Vec<i64> = found.missing.iter().chain(&found.stale).copied().collect();
+            for row in self.store.get(&reinsert)? {
+                self.index.insert(row.id as usize, row.vector)?;
+            }
+            RepairAction::Reinserted
+        };
+        // The snapshot on disk may hold the same damage.
+        *self.last_snapshot.get_mut().unwrap() = None;
+
+        let report = found.report(Some(action));
+        tracing::warn!(
+            missing = report.missing,
+            extra = report.extra,
+            stale = report.stale,
+            "index was inconsistent with storage; {action:?}"
+        );
+        *self.last_check.get_mut().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Verify every stored row's checksum and move corrupt rows to
+    /// quarantine, removing them from the index.
+    ///
+    /// Quarantined rows are counted by `health` until they are restored
+    /// with `restore_quarantined`, rewritten or deleted.
+    pub fn scrub(&mut self) -> Result<ScrubReport, EngineError> {
+        let (checked, quarantined) = self.store.scrub()?;
+        for &id in &quarantined {
+            self.index.delete(id as usize);
+            if let Some(job) = &mut self.rebuild {
+                job.delta.push(DeltaOp::Delete(id as usize));
+            }
+        }
+        Ok(ScrubReport {
+            checked,
+            quarantined,
+        })
+    }
+
+    /// Restore quarantined rows from a backup or replica of this
+    /// collection's database at `source`, which is opened read-only.
+    ///
+    /// Rows the source lacks or holds corrupted too stay in quarantine.
+    pub fn restore_quarantined(
+        &mut self,
+        source: &Path,
+    ) -> Result<QuarantineRestoreReport, EngineError> {
+        let source = SqliteVectorStore::open_read_only(&StorageConfig {
+            path: source.to_path_buf(),
+            dim: self.dim,
+        })?;
+        let restored = self.store.restore_quarantined(&source)?;
+        for row in self.store.get(&restored)? {
+            let id = row.id as usize;
+            if let Some(job) = &mut self.rebuild {
+                job.delta.push(DeltaOp::Upsert(id, row.vector.clone()));
+            }
+            self.index.insert(id, row.vector)?;
+        }
+        self.maintain();
+        Ok(QuarantineRestoreReport {
+            restored,
+            remaining: self.store.quarantine_count()?,
+        })
+    }
+
+    /// Diff storage against the index. With `sample`, only that many vectors
+    /// are compared, starting where the previous sampled check stopped.
+    fn find_discrepancies(&self, sample: Option<usize>) -> Result<Discrepancies, EngineError> {
+        let stored = self.store.ids()?;
+        let stored_set: HashSet<i64> = stored.iter().copied().collect();
+        let indexed: HashSet<i64> = self.index.ids().into_iter().map(|id| id as i64).collect();
+
+        let missing: Vec<i64> = stored
+            .iter()
+            .copied()
+            .filter(|id| !indexed.contains(id))
+            .collect();
+        let mut extra: Vec<i64> = indexed.difference(&stored_set).copied().collect();
+        extra.sort_unstable();
+
+        let common: Vec<i64> = stored
+            .iter()
+            .copied()
+            .filter(|id| indexed.contains(id))
+            .collect();
+        let (wanted, rows): (Vec<i64>, Vec<(i64, Vec<f32>)>) = match sample {
+            Some(n) if n < common.len() => {
+                let start = self.check_cursor.fetch_add(n, Ordering::Relaxed) % common.len();
+                let ids: Vec<i64> = common.iter().cycle().skip(start).take(n).copied().collect();
+                let rows = self
+                    .store
+                    .get(&ids)?
+                    .into_iter()
+                    .map(|row| (row.id, row.vector))
+                    .collect();
+                (ids, rows)
+            }
+            _ => {
+                let (ids, vecs) = self.store.load_all()?;
+                let rows = ids
+                    .into_iter()
+                    .zip(vecs.chunks(self.dim).map(<[f32]>::to_vec))
+                    .filter(|(id, _)| indexed.contains(id))
+                    .collect();
+                (common, rows)
+            }
+        };
+        // Rows that fail their checksum cannot vouch for the indexed vector.
+        let read: HashSet<i64> = rows.iter().map(|(id, _)| *id).collect();
+        let unreadable = wanted.iter().copied().filter(|id| !read.contains(id));
+
+        let ids: Vec<usize> = rows.iter().map(|(id, _)| *id as usize).collect();
+        let indexed_vectors = self.index.vectors(&ids);
+        let stale = rows
+            .iter()
+            .filter(|(id, vector)| {
+                // Compare bit patterns so NaN components still match themselves.
+                !matches!(indexed_vectors.get(&(*id as usize)), Some(v)
+                    if v.len() == vector.len()
+                        && v.iter().zip(vector).all(|(a, b)| a.to_bits() == b.to_bits()))
+            })
+            .map(|(id, _)| *id)
+            .chain(unreadable)
+            .collect();
+
+        Ok(Discrepancies {
+            stored: stored.len(),
+            indexed: indexed.len(),
+            missing,
+            extra,
+            stale,
+            compared: wanted.len(),
+        })
+    }
+
+    /// Estimate the index's recall@k by running a sample of stored vectors
+    /// as queries and comparing the results with an exact scan of storage.
+    ///
+    /// Each call samples different vectors, and storage is scanned a page
+    /// at a time rather than loaded whole. `k` must be at least 1. The
+    /// result is also reported by `health` until the next estimate.
+    pub fn estimate_recall(&self, params: &RecallParams) -> Result<RecallReport, EngineError> {
+        if params.k == 0 {
+            return Err(EngineError::InvalidParameter(
+                "recall k must be at least 1".into(),
+            ));
+        }
+        let metric = self.index.metric();
+        let ids = self.store.ids()?;
+        let k = params.k.min(ids.len());
+
+        let queries = params.queries.min(ids.len());
+        let stride = (ids.len() / queries.max(1)).max(1);
+        let start = self.recall_cursor.fetch_add(1, Ordering::Relaxed);
+        let sample: Vec<i64> = (0..queries)
+            .map(|q| ids[(start + q * stride) % ids.len()])
+            .collect();
+        let queries: Vec<Vec<f32>> = self
+            .store
+            .get(&sample)?
             .into_iter()
-            .map(|(id, distance)| SearchResult { id, distance })
+            .map(|row| row.vector)
+            .collect();
+        let kth = self.kth_distances(&queries, k)?;
+
+        let mut total = 0.0;
+        for (query, &kth) in queries.iter().zip(&kth) {
+            // Count results as hits by distance rather than id, so ties at
+            // the k-th distance are not held against the index.
+            let found: Vec<i64> = self
+                .index
+                .search(query, k)?
+                .into_iter()
+                .map(|(id, _)| id as i64)
+                .collect();
+            let hits = self
+                .store
+                .get(&found)?
+                .iter()
+                .filter(|row| metric.graph_distance(query, &row.vector) <= kth)
+                .count();
+            total += hits.min(k) as f32 / k as f32;
+        }
+
+        let report = RecallReport {
+            k,
+            queries: queries.len(),
+            recall: if queries.is_empty() {
+                1.0
+            } else {
+                total / queries.len() as f32
+            },
+            min_recall: params.min_recall,
+        };
+        *self.last_recall.lock().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Distance from each query to its `k`-th nearest stored vector, found
+    /// by an exact scan of storage a page at a time.
+    fn kth_distances(&self, queries: &[Vec<f32>], k: usize) -> Result<Vec<f32>, EngineError> {
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let metric = self.index.metric();
+        let mut nearest = vec![Vec::with_capacity(k + RECALL_PAGE); queries.len()];
+        let mut after = None;
+        loop {
+            let rows = self.store.rows_after(after, RECALL_PAGE)?;
+            let Some(last) = rows.last() else { break };
+            after = Some(last.id);
+            for (query, best) in queries.iter().zip(&mut nearest) {
+                best.extend(
+                    rows.iter()
+                        .map(|row| metric.graph_distance(query, &row.vector)),
+                );
+                if best.len() > k {
+                    best.select_nth_unstable_by(k - 1, f32::total_cmp);
+                    best.truncate(k);
+                }
+            }
+        }
+        Ok(nearest
+            .iter()
+            .map(|best| best.iter().copied().fold(f32::NEG_INFINITY, f32::max))
             .collect())
     }
 
+    /// Replace the index with one rebuilt from storage.
+    pub fn rebuild_index(&mut self) -> Result<(), EngineError> {
+        let mut index = rebuild(&self.index_cfg, &self.store)?;
+        index.renumber_after(&self.index);
+        #[cfg(any(test, feature = "fault-injection"))]
+        index.set_faults(self.faults.clone());
+        self.index = index;
+        self.compaction = None;
+        *self.last_snapshot.get_mut().unwrap() = None;
+        *self.last_recall.get_mut().unwrap() = None;
+        Ok(())
+    }
+
+    /// Start building a new index from storage on a background thread,
+    /// optionally with different HNSW parameters.
+    ///
+    /// The live index keeps serving reads and writes meanwhile; writes are
+    /// also buffered and replayed onto the new index when `finish_rebuild`
+    /// swaps it in.
+    pub fn start_rebuild(&mut self, params: &RebuildParams) -> Result<RebuildStatus, EngineError> {
+        if self.rebuild.is_some() {
+            return Err(EngineError::RebuildInProgress);
+        }
+        let cfg = params.apply(&self.index_cfg)?;
+        let job = RebuildJob::start(self.storage_cfg.clone(), cfg);
+        let status = job.status();
+        self.rebuild = Some(job);
+        Ok(status)
+    }
+
+    /// Progress of the running rebuild, or the outcome of the last one.
+    pub fn rebuild_status(&self) -> RebuildStatus {
+        match &self.rebuild {
+            Some(job) => job.status(),
+            None => self.last_rebuild.clone(),
+        }
+    }
+
+    pub fn is_rebuilding(&self) -> bool {
+        self.rebuild.is_some()
+    }
+
+    /// Whether a rebuild has finished building and can be swapped in.
+    pub fn rebuild_ready(&self) -> bool {
+        self.rebuild.as_ref().is_some_and(|job| job.is_finished())
+    }
+
+    /// Swap in the index built by `start_rebuild` once it is ready.
+    ///
+    /// Returns `Ok(false)` if no rebuild is ready yet. On failure the live
+    /// index is kept and the error is also recorded in `rebuild_status`.
+    pub fn finish_rebuild(&mut self) -> Result<bool, EngineError> {
+        if !self.rebuild_ready() {
+            return Ok(false);
+        }
+        let job = self.rebuild.take().expect("rebuild_ready checked the job");
+        let mut status = job.status();
+        let cfg = job.cfg.clone();
+
+        match job.join() {
+            Ok(mut index) => {
+                index.renumber_after(&self.index);
+                #[cfg(any(test, feature = "fault-injection"))]
+                index.set_faults(self.faults.clone());
+                self.index = index;
+                self.index_cfg = cfg;
+                // Snapshots are saved with these parameters from now on.
+                if let Err(e) = record_params(&self.store, &self.configured, &self.index_cfg) {
+                    tracing::warn!("cannot record the rebuilt index parameters: {e}");
+                }
+                self.compaction = None;
+                *self.last_snapshot.get_mut().unwrap() = None;
+                *self.last_check.get_mut().unwrap() = None;
+                *self.last_recall.get_mut().unwrap() = None;
+                status.state = RebuildState::Completed;
+                self.last_rebuild = status;
+                Ok(true)
+            }
+            Err(e) => {
+                status.state = RebuildState::Failed;
+                status.error = Some(e.to_string());
+                self.last_rebuild = status;
+                Err(e)
+            }
+        }
+    }
+
     pub fn health(&self) -> HealthReport {
-        basic_index_health(&self.index)
+        let mut report = basic_index_health(&self.index);
+        if let Some(recall) = self.last_recall.lock().unwrap().clone() {
+            if recall.is_degraded() {
+                report.fail("recall-degraded");
+            }
+            report.recall = Some(recall);
+        }
+        if let Some(check) = self.last_check.lock().unwrap().clone() {
+            if !check.is_consistent() && check.repair.is_none() {
+                report.fail("index-inconsistent");
+            }
+            report.consistency = Some(check);
+        }
+        match self.store.quarantine_count() {
+            Ok(0) => {}
+            Ok(n) => {
+                report.fail("rows-quarantined");
+                report.quarantined = n;
+            }
+            Err(e) => tracing::warn!("cannot count quarantined rows: {e}"),
+        }
+        report
     }
 }
 
+/// Build the index by inserting every vector in storage.
+fn rebuild(cfg: &IndexConfig, store: &SqliteVectorStore) -> Result<SegmentedIndex, EngineError> {
+    let mut index = SegmentedIndex::new(cfg)?;
+    // Quarantine corrupt rows first so they are not silently left out.
+    store.scrub()?;
+    let (ids, vecs) = store.load_all()?;
+    for (i, chunk) in vecs.chunks(cfg.dim).enumerate() {
+        let id = ids[i] as usize;
+        index.insert_bulk(id, chunk.to_vec())?;
+    }
+    Ok(index)
+}
+
+/// Load the snapshot in `dir` and apply the writes made after it.
+fn restore(
+    dir: &std::path::Path,
+    cfg: &IndexConfig,
+    store: &SqliteVectorStore,
+) -> Result<Option<(SegmentedIndex, u64)>, EngineError> {
+    let Some((mut index, seq)) = snapshot::load(dir, cfg)? else {
+        return Ok(None);
+    };
+    let current = store.write_seq()?;
+    if seq > current {
+        return Err(SnapshotError::Corrupt(format!(
+            "snapshot covers write {seq} but storage is at {current}"
+        ))
+        .into());
+    }
 
+    // The log must hold every write since the snapshot; a gap means the
+    // snapshot is older than the last truncation and cannot be brought up
+    // to date.
+    let entries = store.log_since(seq)?;
+    if entries.len() as u64 != current - seq || entries.first().is_some_and(|e| e.seq != seq + 1) {
+        return Err(SnapshotError::Corrupt(format!(
+            "operation log does not cover writes {} to {current}",
+            seq + 1
+        ))
+        .into());
+    }
+
+    // Entries name the ids they touched; the rows as stored now are the
+    // result of applying them all in order.
+    let mut touched: Vec<i64> = Vec::new();
+    for entry in &entries {
+        match &entry.op {
+            Op::Add { ids } | Op::Delete { ids } | Op::Quarantine { ids } => touched.extend(ids),
+            Op::SetMeta { .. } => {}
+        }
+    }
+    touched.sort_unstable();
+    touched.dedup();
+    let rows: Vec<StoredRow> = store
+        .get(&touched)?
+        .into_iter()
+        .filter(|row| row.vector.len() == cfg.dim)
+        .collect();
+    let present: HashSet<i64> = rows.iter().map(|row| row.id).collect();
+    for &id in touched.iter().filter(|id| !present.contains(id)) {
+        index.delete(id as usize);
+    }
+    for row in rows {
+        index.insert(row.id as usize, row.vector)?;
+    }
+
+    let stored = store.count_loadable()?;
+    if index.len() != stored {
+        return Err(SnapshotError::Corrupt(format!(
+            "snapshot holds {} vectors after replay, storage has {stored}",
+            index.len()
+        ))
+        .into());
+    }
+    tracing::info!(
+        "loaded index snapshot at write {seq} and replayed {} logged operations",
+        entries.len()
+    );
+    Ok(Some((index, seq)))
+}
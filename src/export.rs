@@ -0,0 +1,225 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::storage::{SqliteVectorStore, StorageConfig, StorageError};
+
+/// Rows read from storage at a time.
+const PAGE: usize = 1_000;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("cannot tell the format of {0}; use .jsonl, .npy or .fvecs")]
+    UnknownFormat(String),
+
+    #[error("expected {expected} rows for the .npy header, found {found}; run a scrub first")]
+    RowCountChanged { expected: usize, found: usize },
+}
+
+/// Output formats for exports. Only `Jsonl` keeps ids and payloads; the
+/// others hold the vectors alone, in ascending id order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One `{"id": .., "vector": [..], "payload": ..}` object per line, as
+    /// read by the JSON Lines import.
+    #[default]
+    Jsonl,
+    /// A `(rows, dim)` little-endian `f32` NumPy array.
+    Npy,
+    /// Little-endian `i32` dimension followed by the `f32` components, per
+    /// vector.
+    Fvecs,
+}
+
+impl ExportFormat {
+    /// The format named by a file's extension.
+    pub fn from_path(path: &Path) -> Result<Self, ExportError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl" | "ndjson") => Ok(Self::Jsonl),
+            Some("npy") => Ok(Self::Npy),
+            Some("fvecs") => Ok(Self::Fvecs),
+            _ => Err(ExportError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    /// HTTP `Content-Type` of the output.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jsonl => "application/x-ndjson",
+            Self::Npy | Self::Fvecs => "application/octet-stream",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Npy => "npy",
+            Self::Fvecs => "fvecs",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(Self::Jsonl),
+            "npy" => Ok(Self::Npy),
+            "fvecs" => Ok(Self::Fvecs),
+            other => Err(format!("unknown format: {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExportReport {
+    /// Vectors written.
+    pub exported: usize,
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    id: i64,
+    vector: &'a [f32],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<&'a Value>,
+}
+
+/// A point-in-time copy of a store, for exporting without holding the
+/// engine. The copy sits next to the database and is deleted on drop.
+pub struct ExportSnapshot {
+    store: Option<SqliteVectorStore>,
+    path: PathBuf,
+}
+
+impl ExportSnapshot {
+    /// Copy `store` with SQLite's online backup API.
+    pub fn take(store: &SqliteVectorStore, cfg: &StorageConfig) -> Result<Self, ExportError> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let mut name = cfg.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".export-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let path = cfg.path.with_file_name(name);
+
+        // Owned from here on, so a failed copy is removed too.
+        let mut snapshot = Self { store: None, path };
+        store.backup_to(&snapshot.path)?;
+        snapshot.store = Some(SqliteVectorStore::open_read_only(&StorageConfig {
+            path: snapshot.path.clone(),
+            dim: cfg.dim,
+        })?);
+        Ok(snapshot)
+    }
+
+    /// Write every row of the copy to `out`; see `write`.
+    pub fn write<W: Write>(
+        &self,
+        format: ExportFormat,
+        out: W,
+    ) -> Result<ExportReport, ExportError> {
+        write(self.store.as_ref().expect("opened in take"), format, out)
+    }
+}
+
+impl Drop for ExportSnapshot {
+    fn drop(&mut self) {
+        // Close the connection before removing its file.
+        self.store.take();
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!("cannot remove export copy {}: {e}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Write every row of `store` to `out` as `format`, a page of rows at a
+/// time. Rows that fail their checksum are skipped.
+pub fn write<W: Write>(
+    store: &SqliteVectorStore,
+    format: ExportFormat,
+    mut out: W,
+) -> Result<ExportReport, ExportError> {
+    // The header needs the row count before the first row.
+    let expected = match format {
+        ExportFormat::Npy => {
+            let rows = store.count_loadable()?;
+            write_npy_header(&mut out, rows, store.dim())?;
+            Some(rows)
+        }
+        _ => None,
+    };
+
+    let mut exported = 0;
+    let mut after = None;
+    loop {
+        let rows = store.rows_after(after, PAGE)?;
+        let Some(last) = rows.last() else { break };
+        after = Some(last.id);
+        for row in &rows {
+            match format {
+                ExportFormat::Jsonl => {
+                    let record = JsonRecord {
+                        id: row.id,
+                        vector: &row.vector,
+                        payload: row.payload.as_ref(),
+                    };
+                    serde_json::to_writer(&mut out, &record)?;
+                    out.write_all(b"\n")?;
+                }
+                ExportFormat::Npy => out.write_all(bytemuck::cast_slice(&le(&row.vector)))?,
+                ExportFormat::Fvecs => {
+                    out.write_all(&(row.vector.len() as i32).to_le_bytes())?;
+                    out.write_all(bytemuck::cast_slice(&le(&row.vector)))?;
+                }
+            }
+        }
+        exported += rows.len();
+    }
+
+    if let Some(expected) = expected.filter(|&n| n != exported) {
+        return Err(ExportError::RowCountChanged {
+            expected,
+            found: exported,
+        });
+    }
+    out.flush()?;
+    Ok(ExportReport { exported })
+}
+
+/// `vector` with each component's bytes in little-endian order.
+fn le(vector: &[f32]) -> Vec<u32> {
+    vector.iter().map(|x| x.to_bits().to_le()).collect()
+}
+
+/// Version 1.0 header: magic, header length, then the dictionary padded
+/// with spaces so the data starts at a multiple of 64 bytes.
+fn write_npy_header(out: &mut impl Write, rows: usize, dim: usize) -> io::Result<()> {
+    let mut dict =
+        format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({rows}, {dim}), }}");
+    let unpadded = 10 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    out.write_all(&(dict.len() as u16).to_le_bytes())?;
+    out.write_all(dict.as_bytes())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Failures that can be injected into `SqliteVectorStore` and `HnswIndex`
+/// to exercise the engine's recovery paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// A write transaction fails with an SQLite I/O error halfway through
+    /// its batch, before committing.
+    StorageWrite,
+    /// The next vector blob written loses its last byte.
+    TruncateBlob,
+    /// The next vector blob written has one byte flipped.
+    CorruptBlob,
+    /// `HnswIndex::insert` panics while holding its id map.
+    IndexInsertPanic,
+    /// `HnswIndex::insert` returns an error without changing the index.
+    IndexInsertError,
+}
+
+/// Armed faults, shared by the components they are injected into.
+///
+/// Each fault fires the number of times it was armed for and then stays
+/// quiet, so a test can fail one specific operation.
+#[derive(Debug, Default)]
+pub struct Faults {
+    armed: Mutex<HashMap<Fault, usize>>,
+}
+
+pub type SharedFaults = Arc<Faults>;
+
+impl Faults {
+    pub fn new() -> SharedFaults {
+        Arc::new(Self::default())
+    }
+
+    /// Make the next `times` occurrences of `fault` fire.
+    pub fn arm(&self, fault: Fault, times: usize) {
+        self.armed.lock().unwrap().insert(fault, times);
+    }
+
+    /// Occurrences of `fault` still to fire.
+    pub fn remaining(&self, fault: Fault) -> usize {
+        self.armed.lock().unwrap().get(&fault).copied().unwrap_or(0)
+    }
+
+    /// Whether `fault` fires at this occurrence; consumes one arming.
+    pub(crate) fn fire(&self, fault: Fault) -> bool {
+        let mut armed = self.armed.lock().unwrap();
+        match armed.get_mut(&fault) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `fault` fires in a component with injected `faults`.
+pub(crate) fn fires(faults: &Option<SharedFaults>, fault: Fault) -> bool {
+    faults.as_ref().is_some_and(|f| f.fire(fault))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armed_faults_fire_the_given_number_of_times() {
+        let faults = Faults::new();
+        assert!(!faults.fire(Fault::StorageWrite));
+
+        faults.arm(Fault::StorageWrite, 2);
+        assert!(faults.fire(Fault::StorageWrite));
+        assert!(!faults.fire(Fault::TruncateBlob));
+        assert_eq!(faults.remaining(Fault::StorageWrite), 1);
+        assert!(faults.fire(Fault::StorageWrite));
+        assert!(!faults.fire(Fault::StorageWrite));
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Predicate over a vector's JSON payload, used to restrict search results.
+///
+/// Serialized in externally tagged form, e.g.
+/// `{"and": [{"eq": {"field": "tenant", "value": 3}}, {"range": {"field": "ts", "gte": 10}}]}`.
+/// `field` is a dot-separated path into the payload object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Eq {
+        field: String,
+        value: Value,
+    },
+    In {
+        field: String,
+        values: Vec<Value>,
+    },
+    Range {
+        field: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gt: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gte: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lt: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lte: Option<f64>,
+    },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Compile the filter into an SQLite boolean expression over the
+    /// `payload` column, pushing bind parameters onto `params`.
+    ///
+    /// Every sub-expression evaluates to 0 or 1 (never NULL), so `not`
+    /// behaves as expected for rows that lack the field or have no payload.
+    pub(crate) fn to_sql(&self, params: &mut Vec<rusqlite::types::Value>) -> String {
+        match self {
+            Filter::Eq { field, value } => eq_sql(field, value, params),
+            Filter::In { field, values } => {
+                if values.is_empty() {
+                    return "0".to_string();
+                }
+                let parts: Vec<String> = values.iter().map(|v| eq_sql(field, v, params)).collect();
+                format!("({})", parts.join(" OR "))
+            }
+            Filter::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } => {
+                let path = bind(params, json_path(field));
+                let mut parts = vec![format!(
+                    "COALESCE(json_type(payload, {path}) IN ('integer', 'real'), 0)"
+                )];
+                for (op, bound) in [(">", gt), (">=", gte), ("<", lt), ("<=", lte)] {
+                    if let Some(bound) = bound {
+                        let b = bind(params, rusqlite::types::Value::Real(*bound));
+                        parts.push(format!("json_extract(payload, {path}) {op} {b}"));
+                    }
+                }
+                format!("({})", parts.join(" AND "))
+            }
+            Filter::And(filters) => {
+                if filters.is_empty() {
+                    return "1".to_string();
+                }
+                let parts: Vec<String> = filters.iter().map(|f| f.to_sql(params)).collect();
+                format!("({})", parts.join(" AND "))
+            }
+            Filter::Or(filters) => {
+                if filters.is_empty() {
+                    return "0".to_string();
+                }
+                let parts: Vec<String> = filters.iter().map(|f| f.to_sql(params)).collect();
+                format!("({})", parts.join(" OR "))
+            }
+            Filter::Not(inner) => format!("(NOT {})", inner.to_sql(params)),
+        }
+    }
+}
+
+fn eq_sql(field: &str, value: &Value, params: &mut Vec<rusqlite::types::Value>) -> String {
+    use rusqlite::types::Value as Sql;
+
+    let path = bind(params, json_path(field));
+    let typ = format!("json_type(payload, {path})");
+    let sql = match value {
+        Value::Null => format!("{typ} = 'null'"),
+        Value::Bool(true) => format!("{typ} = 'true'"),
+        Value::Bool(false) => format!("{typ} = 'false'"),
+        Value::Number(n) => {
+            let v = match n.as_i64() {
+                Some(i) => Sql::Integer(i),
+                None => Sql::Real(n.as_f64().unwrap_or(f64::NAN)),
+            };
+            let v = bind(params, v);
+            format!("{typ} IN ('integer', 'real') AND json_extract(payload, {path}) = {v}")
+        }
+        Value::String(s) => {
+            let v = bind(params, Sql::Text(s.clone()));
+            format!("{typ} = 'text' AND json_extract(payload, {path}) = {v}")
+        }
+        Value::Array(_) | Value::Object(_) => {
+            let v = bind(params, Sql::Text(value.to_string()));
+            format!("{typ} IN ('array', 'object') AND json_extract(payload, {path}) = json({v})")
+        }
+    };
+    format!("COALESCE({sql}, 0)")
+}
+
+/// Push a parameter and return its `?N` placeholder.
+fn bind(
+    params: &mut Vec<rusqlite::types::Value>,
+    value: impl Into<rusqlite::types::Value>,
+) -> String {
+    params.push(value.into());
+    format!("?{}", params.len())
+}
+
+/// Turn `a.b.c` into the SQLite JSON path `$."a"."b"."c"`.
+fn json_path(field: &str) -> String {
+    let mut path = String::from("$");
+    for segment in field.split('.') {
+        path.push_str(".\"");
+        path.push_str(&segment.replace('"', "\\\""));
+        path.push('"');
+    }
+    path
+}
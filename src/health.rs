@@ -1,29 +1,165 @@
 use serde::Serialize;
 
-use crate::index::HnswIndex;
+use crate::segments::SegmentedIndex;
 
 #[derive(Debug, Serialize)]
 pub struct HealthReport {
     pub ok: bool,
+    /// `ok` or `index-empty` while healthy, otherwise the first failed check.
     pub reason: String,
     pub size: usize,
+    /// Index slots in use: live vectors plus deleted and superseded ones
+    /// that stay in their segment until it is compacted.
+    pub slots: usize,
+    /// Slots each sealed segment is sized for (`hnsw_max_elements`).
+    pub max_elements: usize,
+    /// Slots the mutable segment takes before it is sealed.
+    pub mutable_segment_capacity: usize,
+    /// Sealed segments plus the mutable one.
+    pub segments: usize,
+    /// Stored rows that failed verification and await restoring.
+    pub quarantined: usize,
+    /// Conditions that do not make the index unhealthy yet.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Result of the most recent storage/index consistency check, if any ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consistency: Option<ConsistencyReport>,
+    /// Result of the most recent recall estimate, if any ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recall: Option<RecallReport>,
 }
 
-pub fn basic_index_health(index: &HnswIndex) -> HealthReport {
-    if index.is_empty() {
-        return HealthReport {
-            ok: true,
-            reason: "index-empty".to_string(),
-            size: 0,
-        };
+impl HealthReport {
+    /// Mark the report unhealthy. The first failure keeps its reason.
+    pub fn fail(&mut self, reason: &str) {
+        if self.ok {
+            self.ok = false;
+            self.reason = reason.to_string();
+        }
     }
+}
 
-    // For now, assume that if we can query dim and len without panicking, it's fine.
-    HealthReport {
-        ok: true,
-        reason: "ok".to_string(),
-        size: index.len(),
+/// Search quality estimated by comparing index results against an exact scan
+/// of storage for a sample of stored vectors used as queries.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecallReport {
+    pub k: usize,
+    /// Sample queries run.
+    pub queries: usize,
+    /// Mean fraction of the exact top `k` found by the index, from 0 to 1.
+    pub recall: f32,
+    /// Recall below this marks the index as degraded.
+    pub min_recall: f32,
+}
+
+impl RecallReport {
+    pub fn is_degraded(&self) -> bool {
+        self.recall < self.min_recall
+    }
+}
+
+/// Differences found between `SqliteVectorStore` (the source of truth) and
+/// `HnswIndex`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConsistencyReport {
+    /// Vectors in storage.
+    pub stored: usize,
+    /// Live vectors in the index.
+    pub indexed: usize,
+    /// Stored ids absent from the index.
+    pub missing: usize,
+    /// Indexed ids absent from storage.
+    pub extra: usize,
+    /// Compared ids whose indexed vector differs from the stored one.
+    pub stale: usize,
+    /// Ids whose vectors were compared.
+    pub compared: usize,
+    /// A few of the offending ids, for diagnosis.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<i64>,
+    /// What was done about the findings, if anything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repair: Option<RepairAction>,
+}
+
+/// Outcome of verifying every stored row's checksum.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScrubReport {
+    /// Rows verified.
+    pub checked: usize,
+    /// Ids of the rows that failed and were moved to quarantine.
+    pub quarantined: Vec<i64>,
+}
+
+/// Outcome of restoring quarantined rows from a backup or replica.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QuarantineRestoreReport {
+    /// Ids whose intact rows were copied back.
+    pub restored: Vec<i64>,
+    /// Rows still in quarantine.
+    pub remaining: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairAction {
+    /// Missing and stale vectors were reinserted and extra ones removed.
+    Reinserted,
+    /// The index was rebuilt from storage.
+    Rebuilt,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing == 0 && self.extra == 0 && self.stale == 0
     }
 }
 
+/// Fraction of slots holding deleted or replaced vectors above which the
+/// health report warns.
+const DEAD_SLOT_WARNING: f64 = 0.5;
+
+/// Segment count above which the health report warns; every search queries
+/// each segment, so many of them mean `hnsw_max_elements` is too small for
+/// the data.
+const SEGMENT_WARNING: usize = 64;
+
+pub fn basic_index_health(index: &SegmentedIndex) -> HealthReport {
+    let slots = index.slot_count();
+    let dead = slots - index.len();
+    let mut warnings = Vec::new();
+    if dead > 0 && dead as f64 > slots as f64 * DEAD_SLOT_WARNING {
+        warnings.push(format!(
+            "{dead} of {slots} index slots hold deleted or replaced vectors until compaction"
+        ));
+    }
+    let segments = index.segment_count();
+    if segments > SEGMENT_WARNING {
+        warnings.push(format!(
+            "the index spans {segments} segments of at most {} slots; rebuild it with a larger \
+             hnsw_max_elements to search fewer",
+            index.config().max_elements
+        ));
+    }
 
+    let (reason, size) = if index.is_empty() {
+        ("index-empty", 0)
+    } else {
+        // For now, assume that if we can query dim and len without panicking, it's fine.
+        ("ok", index.len())
+    };
+    HealthReport {
+        ok: true,
+        reason: reason.to_string(),
+        size,
+        slots,
+        max_elements: index.config().max_elements,
+        mutable_segment_capacity: index.mutable_capacity(),
+        segments,
+        quarantined: 0,
+        warnings,
+        consistency: None,
+        recall: None,
+    }
+}
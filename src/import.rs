@@ -0,0 +1,457 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("record {record}: {message}")]
+    Record { record: usize, message: String },
+
+    #[error("invalid npy header: {0}")]
+    NpyHeader(String),
+
+    #[error("cannot tell the format of {0}; pass it explicitly")]
+    UnknownFormat(String),
+}
+
+/// Input formats for bulk imports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// Little-endian `i32` dimension followed by that many `f32`s, per
+    /// vector (the SIFT/GIST benchmark format).
+    Fvecs,
+    /// Like `Fvecs` with `u8` components.
+    Bvecs,
+    /// Like `Fvecs` with `i32` components.
+    Ivecs,
+    /// A two-dimensional NumPy array of floats or integers, in C order.
+    Npy,
+    /// One `{"id": .., "vector": [..], "payload": ..}` object per line.
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// The format named by a file's extension.
+    pub fn from_path(path: &Path) -> Result<Self, ImportError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("fvecs") => Ok(Self::Fvecs),
+            Some("bvecs") => Ok(Self::Bvecs),
+            Some("ivecs") => Ok(Self::Ivecs),
+            Some("npy") => Ok(Self::Npy),
+            Some("jsonl" | "ndjson") => Ok(Self::Jsonl),
+            _ => Err(ImportError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fvecs" => Ok(Self::Fvecs),
+            "bvecs" => Ok(Self::Bvecs),
+            "ivecs" => Ok(Self::Ivecs),
+            "npy" => Ok(Self::Npy),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(format!("unknown format: {other}")),
+        }
+    }
+}
+
+/// Settings for `SelfHealingVectorDb::import_file`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Input format; by default the file extension decides.
+    pub format: Option<ImportFormat>,
+    /// Id of the first vector in formats without ids.
+    pub first_id: i64,
+    /// Vectors written per storage transaction.
+    pub batch_size: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            first_id: 0,
+            batch_size: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    /// Vectors written to storage.
+    pub imported: usize,
+    /// Storage transactions committed.
+    pub batches: usize,
+}
+
+/// One vector read from an import file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportRecord {
+    pub id: i64,
+    pub vector: Vec<f32>,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+/// Read `path` as `format` (or the format its extension names), one record
+/// at a time.
+///
+/// Formats without ids number their vectors from `first_id` in file order.
+pub fn read_file(
+    path: &Path,
+    format: Option<ImportFormat>,
+    first_id: i64,
+) -> Result<Box<dyn Iterator<Item = Result<ImportRecord, ImportError>>>, ImportError> {
+    let format = match format {
+        Some(format) => format,
+        None => ImportFormat::from_path(path)?,
+    };
+    read(BufReader::new(File::open(path)?), format, first_id)
+}
+
+/// Like `read_file`, from any reader.
+pub fn read<R: BufRead + 'static>(
+    mut reader: R,
+    format: ImportFormat,
+    first_id: i64,
+) -> Result<Box<dyn Iterator<Item = Result<ImportRecord, ImportError>>>, ImportError> {
+    let with_ids = move |(i, vector): (usize, Result<Vec<f32>, ImportError>)| {
+        Ok(ImportRecord {
+            id: first_id + i as i64,
+            vector: vector?,
+            payload: Value::Null,
+        })
+    };
+    Ok(match format {
+        ImportFormat::Fvecs => {
+            Box::new(VecsReader::new(reader, 4, f32_le).enumerate().map(with_ids))
+        }
+        ImportFormat::Bvecs => Box::new(
+            VecsReader::new(reader, 1, |b| b[0] as f32)
+                .enumerate()
+                .map(with_ids),
+        ),
+        ImportFormat::Ivecs => Box::new(
+            VecsReader::new(reader, 4, |b| i32_le(b) as f32)
+                .enumerate()
+                .map(with_ids),
+        ),
+        ImportFormat::Npy => {
+            let header = NpyHeader::read(&mut reader)?;
+            Box::new(
+                NpyReader {
+                    reader,
+                    header,
+                    row: 0,
+                }
+                .enumerate()
+                .map(with_ids),
+            )
+        }
+        ImportFormat::Jsonl => Box::new(reader.lines().enumerate().filter_map(|(i, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                return None;
+            }
+            Some(
+                serde_json::from_str(&line).map_err(|e| ImportError::Record {
+                    record: i,
+                    message: e.to_string(),
+                }),
+            )
+        })),
+    })
+}
+
+fn f32_le(b: &[u8]) -> f32 {
+    f32::from_le_bytes(b.try_into().unwrap())
+}
+
+fn i32_le(b: &[u8]) -> i32 {
+    i32::from_le_bytes(b.try_into().unwrap())
+}
+
+/// Reads the `*vecs` formats: each vector is prefixed with its dimension.
+struct VecsReader<R> {
+    reader: R,
+    width: usize,
+    decode: fn(&[u8]) -> f32,
+    record: usize,
+}
+
+impl<R: Read> VecsReader<R> {
+    fn new(reader: R, width: usize, decode: fn(&[u8]) -> f32) -> Self {
+        Self {
+            reader,
+            width,
+            decode,
+            record: 0,
+        }
+    }
+
+    fn next_vector(&mut self) -> Result<Option<Vec<f32>>, ImportError> {
+        let mut dim = [0u8; 4];
+        if !read_exact_or_eof(&mut self.reader, &mut dim)? {
+            return Ok(None);
+        }
+        let dim = i32::from_le_bytes(dim);
+        if dim <= 0 {
+            return Err(ImportError::Record {
+                record: self.record,
+                message: format!("invalid dimension {dim}"),
+            });
+        }
+        let mut bytes = vec![0u8; dim as usize * self.width];
+        self.reader
+            .read_exact(&mut bytes)
+            .map_err(|e| ImportError::Record {
+                record: self.record,
+                message: format!("truncated vector: {e}"),
+            })?;
+        Ok(Some(
+            bytes.chunks_exact(self.width).map(self.decode).collect(),
+        ))
+    }
+}
+
+impl<R: Read> Iterator for VecsReader<R> {
+    type Item = Result<Vec<f32>, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next_vector().transpose();
+        self.record += 1;
+        next
+    }
+}
+
+/// Fill `buf`, or return `false` if the reader is already at its end.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => filled += n,
+        }
+    }
+    Ok(true)
+}
+
+/// Element types accepted in `.npy` files, by their `descr`.
+const NPY_TYPES: &[(&str, usize)] = &[
+    ("<f4", 4),
+    ("<f8", 8),
+    ("<i4", 4),
+    ("<i8", 8),
+    ("|u1", 1),
+    ("|i1", 1),
+];
+
+struct NpyHeader {
+    descr: &'static str,
+    width: usize,
+    rows: usize,
+    cols: usize,
+}
+
+impl NpyHeader {
+    /// Parse the magic string and the header dictionary, e.g.
+    /// `{'descr': '<f4', 'fortran_order': False, 'shape': (1000, 128), }`.
+    fn read(reader: &mut impl Read) -> Result<Self, ImportError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic[..6] != b"\x93NUMPY" {
+            return Err(ImportError::NpyHeader("not a .npy file".to_string()));
+        }
+        let len = if magic[6] == 1 {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        } else {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        };
+        let mut dict = vec![0u8; len];
+        reader.read_exact(&mut dict)?;
+        let dict = String::from_utf8_lossy(&dict);
+
+        let field = |key: &str| -> Result<&str, ImportError> {
+            let start = dict
+                .find(&format!("'{key}':"))
+                .ok_or_else(|| ImportError::NpyHeader(format!("no '{key}' in {dict}")))?;
+            Ok(dict[start + key.len() + 3..].trim_start())
+        };
+        let descr = field("descr")?;
+        let (descr, width) = NPY_TYPES
+            .iter()
+            .find(|(name, _)| descr.starts_with(&format!("'{name}'")))
+            .copied()
+            .ok_or_else(|| ImportError::NpyHeader(format!("unsupported element type in {dict}")))?;
+        if field("fortran_order")?.starts_with("True") {
+            return Err(ImportError::NpyHeader(
+                "Fortran-order arrays are not supported".to_string(),
+            ));
+        }
+        let shape = field("shape")?;
+        let shape = &shape[1..shape.find(')').unwrap_or(shape.len())];
+        let dims: Vec<usize> = shape
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| ImportError::NpyHeader(format!("invalid shape in {dict}")))?;
+        let [rows, cols] = dims[..] else {
+            return Err(ImportError::NpyHeader(format!(
+                "expected a two-dimensional array, got shape ({shape})"
+            )));
+        };
+        Ok(Self {
+            descr,
+            width,
+            rows,
+            cols,
+        })
+    }
+
+    fn decode(&self, b: &[u8]) -> f32 {
+        match self.descr {
+            "<f4" => f32_le(b),
+            "<f8" => f64::from_le_bytes(b.try_into().unwrap()) as f32,
+            "<i4" => i32_le(b) as f32,
+            "<i8" => i64::from_le_bytes(b.try_into().unwrap()) as f32,
+            "|u1" => b[0] as f32,
+            _ => b[0] as i8 as f32,
+        }
+    }
+}
+
+struct NpyReader<R> {
+    reader: R,
+    header: NpyHeader,
+    row: usize,
+}
+
+impl<R: Read> Iterator for NpyReader<R> {
+    type Item = Result<Vec<f32>, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row == self.header.rows {
+            return None;
+        }
+        let mut bytes = vec![0u8; self.header.cols * self.header.width];
+        let row = self.row;
+        self.row += 1;
+        Some(match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(bytes
+                .chunks_exact(self.header.width)
+                .map(|b| self.header.decode(b))
+                .collect()),
+            Err(e) => Err(ImportError::Record {
+                record: row,
+                message: format!("truncated row: {e}"),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(bytes: Vec<u8>, format: ImportFormat) -> Vec<ImportRecord> {
+        read(io::Cursor::new(bytes), format, 10)
+            .expect("reader")
+            .collect::<Result<_, _>>()
+            .expect("records")
+    }
+
+    #[test]
+    fn vecs_files_are_read_with_positional_ids() {
+        let mut fvecs = Vec::new();
+        for v in [[1.0_f32, 2.0], [3.0, 4.0]] {
+            fvecs.extend(2_i32.to_le_bytes());
+            fvecs.extend(v.iter().flat_map(|x| x.to_le_bytes()));
+        }
+        let records = collect(fvecs, ImportFormat::Fvecs);
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            (records[1].id, records[1].vector.clone()),
+            (11, vec![3.0, 4.0])
+        );
+
+        let bvecs = [3_i32.to_le_bytes().to_vec(), vec![0, 128, 255]].concat();
+        assert_eq!(
+            collect(bvecs, ImportFormat::Bvecs)[0].vector,
+            vec![0.0, 128.0, 255.0]
+        );
+
+        let truncated = [2_i32.to_le_bytes().to_vec(), vec![0; 5]].concat();
+        let err = read(io::Cursor::new(truncated), ImportFormat::Fvecs, 0)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{err}");
+    }
+
+    #[test]
+    fn npy_headers_are_parsed_and_rows_converted() {
+        let dict = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }";
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend((dict.len() as u16).to_le_bytes());
+        npy.extend(dict.as_bytes());
+        npy.extend((0..6).flat_map(|i| (i as f64 * 0.5).to_le_bytes()));
+
+        let records = collect(npy, ImportFormat::Npy);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].vector, vec![1.5, 2.0, 2.5]);
+
+        let dict = "{'descr': '<f4', 'fortran_order': False, 'shape': (4,), }";
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend((dict.len() as u16).to_le_bytes());
+        npy.extend(dict.as_bytes());
+        assert!(matches!(
+            read(io::Cursor::new(npy), ImportFormat::Npy, 0),
+            Err(ImportError::NpyHeader(_))
+        ));
+    }
+
+    #[test]
+    fn json_lines_carry_ids_and_payloads() {
+        let jsonl = "{\"id\": 7, \"vector\": [1, 2], \"payload\": {\"tag\": \"a\"}}\n\n{\"id\": 9, \"vector\": [3, 4]}\n";
+        let records = collect(jsonl.as_bytes().to_vec(), ImportFormat::Jsonl);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].payload, serde_json::json!({ "tag": "a" }));
+        assert_eq!((records[1].id, &records[1].payload), (9, &Value::Null));
+
+        let err = read(
+            io::Cursor::new(b"{\"id\": 1}\n".to_vec()),
+            ImportFormat::Jsonl,
+            0,
+        )
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+        assert!(err.to_string().starts_with("record 0:"), "{err}");
+    }
+}
@@ -1,17 +1,146 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufWriter, Read, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::RwLock;
+
+use hnsw_rs::hnswio::{load_description, load_hnsw_with_dist, DumpMode, HnswIO};
 use hnsw_rs::prelude::*;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(any(test, feature = "fault-injection"))]
+use crate::faults::{fires, Fault, SharedFaults};
+
+/// Most candidates a filtered search considers before settling for fewer
+/// than `k` matches.
+pub const MAX_FILTER_CANDIDATES: usize = 16_384;
+
 #[derive(Debug, Error)]
 pub enum IndexError {
     #[error("dimension mismatch: expected {expected}, got {got}")]
     DimMismatch { expected: usize, got: usize },
+
+    #[error("index snapshot error: {0}")]
+    Snapshot(String),
+
+    /// Raised by `Fault::IndexInsertError`.
+    #[error("injected fault: {0}")]
+    #[cfg(any(test, feature = "fault-injection"))]
+    Injected(String),
+}
+
+/// Distance metric used to build and search the index.
+///
+/// `SearchResult.distance` is always "smaller is closer":
+/// - `l2`: Euclidean distance
+/// - `cosine`: `1 - cos(a, b)`
+/// - `dot`: negative inner product, `-(a · b)`
+/// - `l1`: Manhattan distance
+/// - `hamming`: fraction of differing components (for 0/1 binary codes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    L2,
+    Cosine,
+    Dot,
+    L1,
+    Hamming,
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::L2 => "l2",
+            Metric::Cosine => "cosine",
+            Metric::Dot => "dot",
+            Metric::L1 => "l1",
+            Metric::Hamming => "hamming",
+        }
+    }
+
+    /// Distance between two vectors as the graph orders them (smaller is closer).
+    pub fn graph_distance(self, a: &[f32], b: &[f32]) -> f32 {
+        MetricDistance(self).eval(a, b)
+    }
+
+    /// Convert a graph distance into the distance reported to callers.
+    pub(crate) fn reported(&self, graph_distance: f32) -> f32 {
+        match self {
+            Metric::Dot => -inner_product_from_distance(graph_distance),
+            _ => graph_distance,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "l2" => Ok(Metric::L2),
+            "cosine" => Ok(Metric::Cosine),
+            "dot" => Ok(Metric::Dot),
+            "l1" => Ok(Metric::L1),
+            "hamming" => Ok(Metric::Hamming),
+            other => Err(format!("unknown metric: {other}")),
+        }
+    }
+}
+
+/// `hnsw_rs` distance that dispatches on `Metric`, so a single concrete
+/// `Hnsw` type serves every metric.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricDistance(pub Metric);
+
+impl Distance<f32> for MetricDistance {
+    fn eval(&self, va: &[f32], vb: &[f32]) -> f32 {
+        match self.0 {
+            Metric::L2 => DistL2.eval(va, vb),
+            Metric::Cosine => DistCosine.eval(va, vb),
+            Metric::Dot => inner_product_distance(va, vb),
+            Metric::L1 => DistL1.eval(va, vb),
+            Metric::Hamming => DistHamming.eval(va, vb),
+        }
+    }
+}
+
+/// `hnsw_rs` requires non-negative distances, and its `DistDot` assumes
+/// unit-length vectors. Map the raw inner product onto a positive value
+/// that decreases as the inner product grows, keeping precision for large
+/// products: `1 / (1 + dot)` for `dot >= 0`, `1 - dot` otherwise.
+fn inner_product_distance(va: &[f32], vb: &[f32]) -> f32 {
+    let dot: f32 = va.iter().zip(vb).map(|(a, b)| a * b).sum();
+    if dot >= 0.0 {
+        1.0 / (1.0 + dot)
+    } else {
+        1.0 - dot
+    }
+}
+
+fn inner_product_from_distance(d: f32) -> f32 {
+    if d <= 1.0 {
+        1.0 / d - 1.0
+    } else {
+        1.0 - d
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct IndexConfig {
     pub dim: usize,
+    pub metric: Metric,
     /// Maximum number of elements the index is expected to hold.
     pub max_elements: usize,
+    /// Slots the mutable segment of a `SegmentedIndex` fills before it is
+    /// sealed; at most `max_elements`.
+    pub mutable_segment_capacity: usize,
     /// HNSW parameter: number of neighbors in layers.
     pub m: usize,
     /// HNSW parameter: construction effort.
@@ -20,12 +149,81 @@ pub struct IndexConfig {
     pub ef_search: usize,
 }
 
+impl IndexConfig {
+    /// Why no index can be built with this configuration, one message per
+    /// setting, named as in `EngineConfig`. Empty when it is usable.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, value) in [
+            ("dim", self.dim),
+            ("hnsw_max_elements", self.max_elements),
+            ("mutable_segment_capacity", self.mutable_segment_capacity),
+            ("hnsw_m", self.m),
+            ("hnsw_ef_construction", self.ef_construction),
+            ("hnsw_ef_search", self.ef_search),
+        ] {
+            if value == 0 {
+                problems.push(format!("{name} must be greater than 0"));
+            }
+        }
+        // hnsw_rs stores neighbour lists in tables of at most 256 entries.
+        if self.m > 256 {
+            problems.push(format!("hnsw_m must be at most 256, got {}", self.m));
+        }
+        problems
+    }
+}
+
 /// Thin wrapper around `hnsw_rs::Hnsw` to keep the rest of the codebase decoupled
 /// from the concrete ANN implementation.
+///
+/// `hnsw_rs` has no point removal or update, so every insert gets a fresh
+/// internal slot and `IdMap` tracks which slot is live for each external id.
+/// Slots that were deleted or superseded by an update are filtered out of
+/// search results.
 pub struct HnswIndex {
     dim: usize,
+    metric: Metric,
+    max_elements: usize,
     ef_search: usize,
-    hnsw: Hnsw<f32, DistL2>,
+    hnsw: Hnsw<f32, MetricDistance>,
+    ids: RwLock<IdMap>,
+    #[cfg(any(test, feature = "fault-injection"))]
+    faults: Option<SharedFaults>,
+}
+
+#[derive(Default)]
+struct IdMap {
+    /// Internal slot -> external id, for every point ever inserted.
+    external: Vec<usize>,
+    /// External id -> its live slot.
+    live: HashMap<usize, usize>,
+}
+
+/// `IdMap` contents saved next to a graph dump, so that slots in a reloaded
+/// graph can be mapped back to external ids.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdMapState {
+    pub external: Vec<usize>,
+    /// `(external id, slot)` for every live id.
+    pub live: Vec<(usize, usize)>,
+}
+
+impl IdMap {
+    fn is_live(&self, slot: usize) -> bool {
+        self.live.get(&self.external[slot]) == Some(&slot)
+    }
+
+    fn stale(&self) -> usize {
+        self.external.len() - self.live.len()
+    }
+
+    fn state(&self) -> IdMapState {
+        IdMapState {
+            external: self.external.clone(),
+            live: self.live.iter().map(|(&id, &slot)| (id, slot)).collect(),
+        }
+    }
 }
 
 impl HnswIndex {
@@ -33,18 +231,23 @@ impl HnswIndex {
         // hnsw_rs 0.1.x signature:
         // Hnsw::new(max_nb_connection, max_elements, max_layer, ef_construction, dist_fn)
         let max_layer = 16;
-        let hnsw = Hnsw::<f32, DistL2>::new(
+        let hnsw = Hnsw::<f32, MetricDistance>::new(
             cfg.m,
             cfg.max_elements,
             max_layer,
             cfg.ef_construction,
-            DistL2 {},
+            MetricDistance(cfg.metric),
         );
 
         Ok(Self {
             dim: cfg.dim,
+            metric: cfg.metric,
+            max_elements: cfg.max_elements,
             ef_search: cfg.ef_search,
             hnsw,
+            ids: RwLock::new(IdMap::default()),
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
         })
     }
 
@@ -52,42 +255,274 @@ impl HnswIndex {
         self.dim
     }
 
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Capacity the index was sized for. `hnsw_rs` only uses it to
+    /// preallocate, so inserting past it works; `SegmentedIndex` starts a new
+    /// segment instead.
+    pub fn max_elements(&self) -> usize {
+        self.max_elements
+    }
+
+    /// Inject failures into later inserts (see `Fault`).
+    #[cfg(any(test, feature = "fault-injection"))]
+    pub fn set_faults(&mut self, faults: Option<SharedFaults>) {
+        self.faults = faults;
+    }
+
+    /// Number of live (non-deleted, non-superseded) points in the index.
     pub fn len(&self) -> usize {
-        self.hnsw.get_nb_point()
+        self.ids.read().unwrap().live.len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    pub fn insert(&self, id: usize, vector: Vec<f32>) -> Result<(), IndexError> {
+    /// Number of internal slots, including deleted and superseded ones.
+    pub fn slot_count(&self) -> usize {
+        self.ids.read().unwrap().external.len()
+    }
+
+    /// External ids of every live point, in no particular order.
+    pub fn ids(&self) -> Vec<usize> {
+        self.ids.read().unwrap().live.keys().copied().collect()
+    }
+
+    /// Indexed vectors for the live points among `ids`.
+    ///
+    /// `hnsw_rs` has no lookup by data id, so this scans every point.
+    pub fn vectors(&self, ids: &[usize]) -> HashMap<usize, Vec<f32>> {
+        let map = self.ids.read().unwrap();
+        let wanted: HashSet<usize> = ids.iter().copied().collect();
+        self.hnsw
+            .get_point_indexation()
+            .into_iter()
+            .filter(|point| map.is_live(point.get_origin_id()))
+            .map(|point| (map.external[point.get_origin_id()], point))
+            .filter(|(id, _)| wanted.contains(id))
+            .map(|(id, point)| (id, point.get_v().to_vec()))
+            .collect()
+    }
+
+    /// Write the graph (with vectors) to `graph` and `data` using the
+    /// `hnsw_rs` dump format, and return the id bookkeeping to store with it.
+    pub fn dump<W: Write>(&self, graph: W, data: W) -> Result<IdMapState, IndexError> {
+        let ids = self.ids.read().unwrap();
+        if ids.external.is_empty() {
+            return Err(IndexError::Snapshot(
+                "cannot dump an empty index".to_string(),
+            ));
+        }
+
+        let mut graph = BufWriter::new(graph);
+        let mut data = BufWriter::new(data);
+        self.hnsw
+            .dump(DumpMode::Full, &mut graph, &mut data)
+            .map_err(IndexError::Snapshot)?;
+        graph
+            .flush()
+            .map_err(|e| IndexError::Snapshot(e.to_string()))?;
+        data.flush()
+            .map_err(|e| IndexError::Snapshot(e.to_string()))?;
+
+        Ok(ids.state())
+    }
+
+    /// The id bookkeeping `dump` would return, without writing the graph.
+    ///
+    /// Deletes only change this part, so it can be paired with an earlier
+    /// dump of a graph that has had no inserts since.
+    pub fn id_map(&self) -> IdMapState {
+        self.ids.read().unwrap().state()
+    }
+
+    /// Rebuild an index from a `dump`, without reinserting every point.
+    ///
+    /// The dump must have been taken with the same dimension, metric and
+    /// graph parameters as `cfg`.
+    pub fn load(
+        cfg: &IndexConfig,
+        graph: &mut dyn Read,
+        data: &mut dyn Read,
+        state: IdMapState,
+    ) -> Result<Self, IndexError> {
+        // hnsw_rs asserts on malformed input rather than returning errors.
+        let hnsw = catch_unwind(AssertUnwindSafe(|| {
+            let description = load_description(graph)?;
+            if description.dimension != cfg.dim
+                || description.max_nb_connection as usize != cfg.m
+                || description.ef != cfg.ef_construction
+                || description.nb_point != state.external.len()
+            {
+                return Err(std::io::Error::other(
+                    "dump parameters do not match the index configuration",
+                ));
+            }
+            load_hnsw_with_dist(graph, &description, MetricDistance(cfg.metric), data)
+        }))
+        .map_err(|_| IndexError::Snapshot("malformed graph dump".to_string()))?
+        .map_err(|e| IndexError::Snapshot(e.to_string()))?;
+
+        let slots = state.external.len();
+        let mut ids = IdMap {
+            external: state.external,
+            live: HashMap::with_capacity(state.live.len()),
+        };
+        for (id, slot) in state.live {
+            if slot >= slots || ids.external[slot] != id || ids.live.insert(id, slot).is_some() {
+                return Err(IndexError::Snapshot(format!(
+                    "inconsistent id map for id {id}"
+                )));
+            }
+        }
+
+        Ok(Self {
+            dim: cfg.dim,
+            metric: cfg.metric,
+            max_elements: cfg.max_elements,
+            ef_search: cfg.ef_search,
+            hnsw,
+            ids: RwLock::new(ids),
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
+        })
+    }
+
+    /// Insert or replace the vector for `id`.
+    ///
+    /// Returns `true` if an existing live vector for `id` was replaced.
+    pub fn insert(&self, id: usize, vector: Vec<f32>) -> Result<bool, IndexError> {
         if vector.len() != self.dim {
             return Err(IndexError::DimMismatch {
                 expected: self.dim,
                 got: vector.len(),
             });
         }
-        // HNSW insert takes (&Vec<T>, external_id)
-        self.hnsw.insert((&vector, id));
-        Ok(())
+
+        #[cfg(any(test, feature = "fault-injection"))]
+        if fires(&self.faults, Fault::IndexInsertError) {
+            return Err(IndexError::Injected(format!(
+                "index insert of id {id} failed"
+            )));
+        }
+
+        let mut ids = self.ids.write().unwrap();
+        let slot = ids.external.len();
+        ids.external.push(id);
+        #[cfg(any(test, feature = "fault-injection"))]
+        if fires(&self.faults, Fault::IndexInsertPanic) {
+            panic!("injected fault: index insert of id {id} panicked");
+        }
+
+        // HNSW insert takes (&Vec<T>, data_id); we pass our internal slot.
+        self.hnsw.insert((&vector, slot));
+
+        Ok(ids.live.insert(id, slot).is_some())
+    }
+
+    /// Mark `id` as deleted so it is no longer returned by `search`.
+    ///
+    /// Returns `false` if the id was not live in the index.
+    pub fn delete(&self, id: usize) -> bool {
+        self.ids.write().unwrap().live.remove(&id).is_some()
     }
 
     pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(usize, f32)>, IndexError> {
+        self.search_filtered(query, k, |_| true)
+    }
+
+    /// Search for the `k` nearest live points whose external id passes `allow`.
+    ///
+    /// `hnsw_rs` 0.1 has no filtered search, so the predicate is applied to the
+    /// candidates inside the index and the candidate list is widened until `k`
+    /// matches are found, up to `MAX_FILTER_CANDIDATES`. Past that the search
+    /// returns the matches it has, so a filter that rejects nearly every
+    /// candidate may yield fewer than `k` results; callers with a selective
+    /// filter should score its matches exactly instead.
+    pub fn search_filtered<F>(
+        &self,
+        query: &[f32],
+        k: usize,
+        allow: F,
+    ) -> Result<Vec<(usize, f32)>, IndexError>
+    where
+        F: Fn(usize) -> bool,
+    {
         if query.len() != self.dim {
             return Err(IndexError::DimMismatch {
                 expected: self.dim,
                 got: query.len(),
             });
         }
-        // HNSW search signature: search(&[T], knbn, ef_arg) -> Vec<Neighbour>
-        let results = self.hnsw.search(query, k, self.ef_search);
 
-        let neighbors = results
+        let ids = self.ids.read().unwrap();
+        let total = ids.external.len();
+        let live = ids.live.len();
+        if live == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Over-fetch by the share of stale slots, so that filtering them out
+        // usually still leaves k results; the loop below widens if not.
+        let limit = total.min(MAX_FILTER_CANDIDATES.max(k));
+        let mut knbn = (k + k * ids.stale() / live).min(limit);
+
+        loop {
+            // HNSW search signature: search(&[T], knbn, ef_arg) -> Vec<Neighbour>
+            let results = self.hnsw.search(query, knbn, self.ef_search.max(knbn));
+            let exhausted = knbn >= total;
+            let capped = knbn >= limit;
+
+            let neighbors: Vec<(usize, f32)> = results
+                .into_iter()
+                .filter(|neigh| ids.is_live(neigh.d_id))
+                .map(|neigh| (ids.external[neigh.d_id], neigh.distance))
+                .filter(|(id, _)| allow(*id))
+                .take(k)
+                .map(|(id, d)| (id, self.metric.reported(d)))
+                .collect();
+
+            if neighbors.len() >= k {
+                return Ok(neighbors);
+            }
+            if exhausted {
+                // The graph search may not reach every point; an exact scan
+                // guarantees we return every match when there are fewer than k.
+                // It is bounded, as a graph this small is within the cap.
+                return Ok(self.exact_search(&ids, query, k, &allow));
+            }
+            if capped {
+                return Ok(neighbors);
+            }
+            knbn = (knbn * 2).max(k).min(limit);
+        }
+    }
+
+    /// Brute-force k-NN over every live point that passes `allow`.
+    fn exact_search<F>(&self, ids: &IdMap, query: &[f32], k: usize, allow: &F) -> Vec<(usize, f32)>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let dist = self.hnsw.get_distance();
+        let mut scored: Vec<(usize, f32)> = self
+            .hnsw
+            .get_point_indexation()
             .into_iter()
-            .map(|neigh| (neigh.d_id as usize, neigh.distance))
+            .filter(|point| ids.is_live(point.get_origin_id()))
+            .map(|point| (ids.external[point.get_origin_id()], point))
+            .filter(|(id, _)| allow(*id))
+            .map(|(id, point)| (id, dist.eval(query, point.get_v())))
             .collect();
 
-        Ok(neighbors)
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.truncate(k);
+        for (_, d) in &mut scored {
+            *d = self.metric.reported(*d);
+        }
+        scored
     }
 }
 
@@ -99,7 +534,9 @@ mod tests {
     fn insert_and_search_returns_self_as_nearest() {
         let cfg = IndexConfig {
             dim: 4,
+            metric: Metric::L2,
             max_elements: 16,
+            mutable_segment_capacity: 16,
             m: 8,
             ef_construction: 16,
             ef_search: 16,
@@ -123,7 +560,9 @@ mod tests {
     fn dim_mismatch_on_insert_errors() {
         let cfg = IndexConfig {
             dim: 4,
+            metric: Metric::L2,
             max_elements: 16,
+            mutable_segment_capacity: 16,
             m: 8,
             ef_construction: 16,
             ef_search: 16,
@@ -132,8 +571,165 @@ mod tests {
         let index = HnswIndex::new(&cfg).expect("index created");
 
         let err = index.insert(1, vec![1.0, 0.0, 0.0]).unwrap_err();
-        matches!(err, IndexError::DimMismatch { expected: 4, got: 3 });
+        matches!(
+            err,
+            IndexError::DimMismatch {
+                expected: 4,
+                got: 3
+            }
+        );
     }
-}
 
+    #[test]
+    fn deleted_ids_are_not_returned_by_search() {
+        let cfg = IndexConfig {
+            dim: 4,
+            metric: Metric::L2,
+            max_elements: 16,
+            mutable_segment_capacity: 16,
+            m: 8,
+            ef_construction: 16,
+            ef_search: 16,
+        };
+
+        let index = HnswIndex::new(&cfg).expect("index created");
+        index.insert(1, vec![1.0, 0.0, 0.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 1.0, 0.0, 0.0]).unwrap();
+
+        assert!(index.delete(1));
+        assert_eq!(index.len(), 1);
+
+        let neighbors = index
+            .search(&[1.0, 0.0, 0.0, 0.0], 2)
+            .expect("search should succeed");
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].0, 2);
+    }
 
+    #[test]
+    fn reinserting_an_id_replaces_its_vector() {
+        let cfg = IndexConfig {
+            dim: 4,
+            metric: Metric::L2,
+            max_elements: 16,
+            mutable_segment_capacity: 16,
+            m: 8,
+            ef_construction: 16,
+            ef_search: 16,
+        };
+
+        let index = HnswIndex::new(&cfg).expect("index created");
+        assert!(!index.insert(1, vec![1.0, 0.0, 0.0, 0.0]).unwrap());
+        index.insert(2, vec![0.0, 1.0, 0.0, 0.0]).unwrap();
+        assert!(index.insert(1, vec![0.0, 0.0, 1.0, 0.0]).unwrap());
+        assert_eq!(index.len(), 2);
+
+        let neighbors = index
+            .search(&[1.0, 0.0, 0.0, 0.0], 3)
+            .expect("search should succeed");
+        let returned: Vec<usize> = neighbors.iter().map(|n| n.0).collect();
+        assert_eq!(returned.len(), 2, "stale vector must not be returned");
+        assert!(returned.contains(&1) && returned.contains(&2));
+
+        let nearest = index.search(&[0.0, 0.0, 1.0, 0.0], 1).unwrap();
+        assert_eq!(nearest[0].0, 1);
+    }
+
+    #[test]
+    fn search_widens_past_deleted_nearest_neighbours() {
+        let cfg = IndexConfig {
+            dim: 2,
+            metric: Metric::L2,
+            max_elements: 256,
+            mutable_segment_capacity: 256,
+            m: 8,
+            ef_construction: 32,
+            ef_search: 16,
+        };
+
+        let index = HnswIndex::new(&cfg).expect("index created");
+        for id in 0..200 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+        // Few stale slots overall, but all of them nearest to the query.
+        for id in 0..20 {
+            index.delete(id);
+        }
+
+        let neighbors = index.search(&[0.0, 0.0], 5).unwrap();
+        let ids: Vec<usize> = neighbors.iter().map(|n| n.0).collect();
+        assert_eq!(ids, vec![20, 21, 22, 23, 24]);
+    }
+
+    #[test]
+    fn selective_filter_still_returns_k_results() {
+        let cfg = IndexConfig {
+            dim: 2,
+            metric: Metric::L2,
+            max_elements: 256,
+            mutable_segment_capacity: 256,
+            m: 8,
+            ef_construction: 32,
+            ef_search: 4,
+        };
+
+        let index = HnswIndex::new(&cfg).expect("index created");
+        for id in 0..200 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+
+        // Only the farthest ids match, so the first candidate batch has none.
+        let neighbors = index
+            .search_filtered(&[0.0, 0.0], 3, |id| id >= 190 && id % 2 == 0)
+            .expect("search should succeed");
+        let returned: Vec<usize> = neighbors.iter().map(|n| n.0).collect();
+        assert_eq!(returned, vec![190, 192, 194]);
+    }
+
+    #[test]
+    fn metrics_rank_and_report_distances() {
+        let make = |metric| {
+            let index = HnswIndex::new(&IndexConfig {
+                dim: 2,
+                metric,
+                max_elements: 16,
+                mutable_segment_capacity: 16,
+                m: 8,
+                ef_construction: 16,
+                ef_search: 16,
+            })
+            .expect("index created");
+            index.insert(1, vec![10.0, 0.0]).unwrap();
+            index.insert(2, vec![0.5, 0.5]).unwrap();
+            index
+        };
+
+        // Under L2 the short vector is closer; under cosine and dot the long,
+        // well-aligned one wins.
+        let query = [1.0, 0.1];
+        assert_eq!(make(Metric::L2).search(&query, 1).unwrap()[0].0, 2);
+        assert_eq!(make(Metric::Cosine).search(&query, 1).unwrap()[0].0, 1);
+
+        let dot = make(Metric::Dot).search(&query, 2).unwrap();
+        assert_eq!(dot[0].0, 1);
+        assert!((dot[0].1 - -10.0).abs() < 1e-4, "dot reports -(a · b)");
+        assert!((dot[1].1 - -0.55).abs() < 1e-4);
+
+        let l1 = make(Metric::L1).search(&[0.0, 0.0], 1).unwrap();
+        assert_eq!(l1[0], (2, 1.0));
+    }
+
+    #[test]
+    fn inner_product_distance_is_positive_and_monotonic() {
+        let dots = [-100.0_f32, -1.0, 0.0, 0.5, 1.0, 1e4];
+        let dists: Vec<f32> = dots
+            .iter()
+            .map(|&d| inner_product_distance(&[d], &[1.0]))
+            .collect();
+        assert!(dists.iter().all(|&d| d > 0.0));
+        assert!(dists.windows(2).all(|w| w[0] > w[1]));
+        for (&dot, &d) in dots.iter().zip(&dists) {
+            assert!((inner_product_from_distance(d) - dot).abs() <= 1e-3 * dot.abs().max(1.0));
+        }
+    }
+}
@@ -1,9 +1,20 @@
+pub mod api;
+pub mod backup;
+pub mod collections;
+pub mod config;
+pub mod embeddings;
 pub mod engine;
+pub mod export;
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod faults;
+pub mod filter;
+pub mod health;
+pub mod import;
 pub mod index;
+pub mod rebuild;
+pub mod segments;
+pub mod snapshot;
 pub mod storage;
-pub mod health;
-pub mod embeddings;
 
 pub use engine::{EngineConfig, SelfHealingVectorDb};
-
-
+pub use index::Metric;
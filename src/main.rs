@@ -1,104 +1,856 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use axum::{
-    extract::State,
+    async_trait,
+    body::{Body, Bytes},
+    extract::{
+        rejection::QueryRejection, ConnectInfo, FromRequestParts, Path, Query, Request, State,
+    },
+    http::{header, request::Parts, StatusCode},
+    middleware::{self, Next},
+    response::Response,
     routing::{get, post},
     Json, Router,
 };
+use clap::Parser;
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
 use tokio::sync::RwLock;
 use tracing_subscriber::EnvFilter;
 
-use self_healing_vector_db::embeddings::DummyEmbedder;
-use self_healing_vector_db::engine::{EngineConfig, SelfHealingVectorDb};
+use self_healing_vector_db::api::{confined_path, ApiError, ApiJson};
+use self_healing_vector_db::backup::BackupManifest;
+use self_healing_vector_db::collections::{CollectionCatalog, CollectionConfig, CollectionError};
+use self_healing_vector_db::config::{CliArgs, Config};
+use self_healing_vector_db::embeddings::{DummyEmbedder, SharedEmbedder};
+use self_healing_vector_db::engine::{
+    AddResult, RecallParams, SearchParams, SearchResult, SelfHealingVectorDb, StoredVector,
+};
+use self_healing_vector_db::export::ExportFormat;
+use self_healing_vector_db::filter::Filter;
+use self_healing_vector_db::health::{
+    ConsistencyReport, HealthReport, QuarantineRestoreReport, ScrubReport,
+};
+use self_healing_vector_db::import::{ImportOptions, ImportReport};
+use self_healing_vector_db::rebuild::{RebuildParams, RebuildState, RebuildStatus};
+
+type SharedEngine = Arc<RwLock<SelfHealingVectorDb>>;
 
 #[derive(Clone)]
 struct AppState {
-    engine: Arc<RwLock<SelfHealingVectorDb>>,
+    /// Default engine behind the unscoped `/add`, `/search`, ... routes.
+    engine: SharedEngine,
+    collections: Arc<RwLock<Collections>>,
+    /// The configured embedder; `None` gives each store a `DummyEmbedder`.
+    embedder: Option<SharedEmbedder>,
+    admin: Arc<Admin>,
+}
+
+/// Who may call the `/admin` routes, and which server files they may touch.
+struct Admin {
+    /// Required as a bearer token; unset, only loopback clients are served.
+    token: Option<String>,
+    /// Backup bundles and restore sources are resolved inside this directory.
+    backup_dir: PathBuf,
+    /// Import files are resolved inside this directory.
+    import_dir: PathBuf,
+}
+
+struct Collections {
+    catalog: CollectionCatalog,
+    engines: HashMap<String, SharedEngine>,
+}
+
+/// The engine a request operates on: the named collection for
+/// `/collections/:name/...` routes, the default engine otherwise.
+struct Engine(SharedEngine);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Engine {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let params = Option::<Path<HashMap<String, String>>>::from_request_parts(parts, state)
+            .await
+            .expect("Option extractor is infallible");
+
+        match params.as_ref().and_then(|Path(p)| p.get("name")) {
+            None => Ok(Engine(state.engine.clone())),
+            Some(name) => {
+                let collections = state.collections.read().await;
+                collections
+                    .engines
+                    .get(name)
+                    .cloned()
+                    .map(Engine)
+                    .ok_or_else(|| {
+                        ApiError::not_found(
+                            "collection_not_found",
+                            format!("collection not found: {name}"),
+                        )
+                    })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateCollectionRequest {
+    name: String,
+    #[serde(flatten)]
+    config: CollectionConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct CollectionInfo {
+    name: String,
+    config: CollectionConfig,
+    health: HealthReport,
+}
+
+#[derive(Debug, Deserialize)]
+struct VectorPath {
+    id: i64,
 }
 
 #[derive(Debug, Deserialize)]
 struct AddRequest {
     ids: Vec<i64>,
     vectors: Vec<f32>,
+    /// Optional JSON payload per id (`null` for none).
+    #[serde(default)]
+    payloads: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddTextRequest {
+    ids: Vec<i64>,
+    texts: Vec<String>,
+    #[serde(default)]
+    payloads: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteRequest {
+    ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteResponse {
+    deleted: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetRequest {
+    ids: Vec<i64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct SearchRequest {
     query: Vec<f32>,
     k: usize,
+    #[serde(default)]
+    with_payload: bool,
+    #[serde(default)]
+    with_text: bool,
+    #[serde(default)]
+    filter: Option<Filter>,
 }
 
-#[derive(Debug, Serialize)]
-struct HealthResponse {
-    ok: bool,
-    reason: String,
-    size: usize,
+#[derive(Debug, Deserialize)]
+struct SearchTextRequest {
+    query: String,
+    k: usize,
+    #[serde(default)]
+    with_payload: bool,
+    #[serde(default)]
+    with_text: bool,
+    #[serde(default)]
+    filter: Option<Filter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackupRequest {
+    /// New or empty directory to write the bundle to, relative to `backup_dir`.
+    dest: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreBackupRequest {
+    /// Bundle written by `POST /admin/backup`, relative to `backup_dir`.
+    source: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportRequest {
+    /// File on the server to load, relative to `import_dir`.
+    path: PathBuf,
+    #[serde(flatten)]
+    options: ImportOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreQuarantineRequest {
+    /// Database file of a backup or replica holding intact copies, relative
+    /// to `backup_dir`.
+    source: PathBuf,
 }
 
 #[tokio::main]
 async fn main() {
+    let args = CliArgs::parse();
+    let config = Config::load(&args).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(2);
+    });
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return;
+    }
+
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
-    let dim = 384;
-    let engine_cfg = EngineConfig {
-        dim,
-        storage_path: "data/vectors.sqlite".into(),
-        hnsw_max_elements: 100_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
-    };
-
-    let embedder = DummyEmbedder { dim };
-    let engine = SelfHealingVectorDb::new(engine_cfg, Some(Arc::new(embedder)))
+    let embedder = config.embedder.build().unwrap_or_else(|e| {
+        eprintln!(
+            "error: cannot set up the {:?} embedder: {e}",
+            config.embedder.kind
+        );
+        std::process::exit(2);
+    });
+    let engine_cfg = config.engine_config();
+    let default_embedder = embedder.clone().unwrap_or_else(|| {
+        Arc::new(DummyEmbedder {
+            dim: engine_cfg.dim,
+        })
+    });
+    let engine = SelfHealingVectorDb::new(engine_cfg, Some(default_embedder))
         .expect("failed to create engine");
 
+    let catalog = CollectionCatalog::open(&config.server.data_dir)
+        .expect("failed to open collection catalog");
+    let mut engines = HashMap::new();
+    for (name, cfg) in catalog.list() {
+        let engine = catalog
+            .open_collection(name, embedder_for(&embedder, cfg.dim))
+            .unwrap_or_else(|e| panic!("failed to open collection {name}: {e}"));
+        engines.insert(name.to_string(), Arc::new(RwLock::new(engine)));
+    }
+
+    let (backup_dir, import_dir) = (config.backup_dir(), config.import_dir());
+    std::fs::create_dir_all(&backup_dir).expect("failed to create the backup directory");
+    std::fs::create_dir_all(&import_dir).expect("failed to create the import directory");
+
     let state = AppState {
         engine: Arc::new(RwLock::new(engine)),
+        collections: Arc::new(RwLock::new(Collections { catalog, engines })),
+        embedder,
+        admin: Arc::new(Admin {
+            token: config.server.admin_token.clone(),
+            backup_dir,
+            import_dir,
+        }),
     };
 
+    tokio::spawn(maintenance_task(state.clone()));
+    if config.server.snapshot_interval_secs > 0 {
+        let interval = Duration::from_secs(config.server.snapshot_interval_secs);
+        tokio::spawn(snapshot_task(state.clone(), interval));
+    }
+    if config.server.consistency_check_interval_secs > 0 {
+        let interval = Duration::from_secs(config.server.consistency_check_interval_secs);
+        tokio::spawn(consistency_task(state.clone(), interval));
+    }
+    if config.server.recall_check_interval_secs > 0 {
+        let interval = Duration::from_secs(config.server.recall_check_interval_secs);
+        tokio::spawn(recall_task(
+            state.clone(),
+            interval,
+            config.recall_params(),
+            config.server.rebuild_below_recall,
+        ));
+    }
+    if config.server.scrub_interval_secs > 0 {
+        let interval = Duration::from_secs(config.server.scrub_interval_secs);
+        tokio::spawn(scrub_task(state.clone(), interval));
+    }
+
+    let admin = Router::new()
+        .route("/admin/repair", post(repair_handler))
+        .route("/admin/scrub", post(scrub_handler))
+        .route("/admin/backup", post(backup_handler))
+        .route("/admin/import", post(import_handler))
+        .route("/admin/export", get(export_handler))
+        .route("/admin/restore", post(restore_backup_handler))
+        .route(
+            "/admin/quarantine/restore",
+            post(restore_quarantine_handler),
+        )
+        .route(
+            "/admin/rebuild",
+            get(rebuild_status_handler).post(start_rebuild_handler),
+        )
+        .route("/collections/:name/admin/repair", post(repair_handler))
+        .route("/collections/:name/admin/scrub", post(scrub_handler))
+        .route("/collections/:name/admin/backup", post(backup_handler))
+        .route("/collections/:name/admin/import", post(import_handler))
+        .route("/collections/:name/admin/export", get(export_handler))
+        .route(
+            "/collections/:name/admin/restore",
+            post(restore_backup_handler),
+        )
+        .route(
+            "/collections/:name/admin/quarantine/restore",
+            post(restore_quarantine_handler),
+        )
+        .route(
+            "/collections/:name/admin/rebuild",
+            get(rebuild_status_handler).post(start_rebuild_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin));
+
     let app = Router::new()
         .route("/add", post(add_handler))
+        .route("/add_text", post(add_text_handler))
+        .route("/delete", post(delete_handler))
         .route("/search", post(search_handler))
+        .route("/search_text", post(search_text_handler))
+        .route("/vectors/get", post(get_vectors_handler))
+        .route("/vectors/:id", get(get_vector_handler))
         .route("/health", get(health_handler))
+        .route(
+            "/collections",
+            get(list_collections_handler).post(create_collection_handler),
+        )
+        .route(
+            "/collections/:name",
+            get(describe_collection_handler).delete(drop_collection_handler),
+        )
+        .route("/collections/:name/add", post(add_handler))
+        .route("/collections/:name/add_text", post(add_text_handler))
+        .route("/collections/:name/delete", post(delete_handler))
+        .route("/collections/:name/search", post(search_handler))
+        .route("/collections/:name/search_text", post(search_text_handler))
+        .route("/collections/:name/vectors/get", post(get_vectors_handler))
+        .route("/collections/:name/vectors/:id", get(get_vector_handler))
+        .merge(admin)
         .with_state(state);
 
-    let addr = "127.0.0.1:3000";
+    let addr = config.server.bind;
     let listener = TcpListener::bind(addr).await.unwrap();
     tracing::info!("listening on http://{}", addr);
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+/// The default engine and every collection's engine, with display names.
+async fn all_engines(state: &AppState) -> Vec<(String, SharedEngine)> {
+    let mut engines = vec![("default".to_string(), state.engine.clone())];
+    engines.extend(
+        state
+            .collections
+            .read()
+            .await
+            .engines
+            .iter()
+            .map(|(name, engine)| (name.clone(), engine.clone())),
+    );
+    engines
+}
+
+/// Swap in background rebuilds and segment compactions as they finish, and
+/// start compactions; the write lock is only held for the swaps themselves.
+async fn maintenance_task(state: AppState) {
+    let mut ticker = tokio::time::interval(Duration::from_millis(250));
+    loop {
+        ticker.tick().await;
+        for (name, engine) in all_engines(&state).await {
+            if !engine.read().await.needs_maintenance() {
+                continue;
+            }
+            let mut engine = engine.write().await;
+            let rebuilt = engine.rebuild_ready();
+            tokio::task::block_in_place(|| engine.maintain());
+            let status = engine.rebuild_status();
+            match status.state {
+                RebuildState::Completed if rebuilt => tracing::info!(
+                    "index rebuild for {name} finished: {} vectors, {} writes replayed",
+                    status.total,
+                    status.pending_writes
+                ),
+                RebuildState::Failed if rebuilt => tracing::warn!(
+                    "index rebuild for {name} failed: {}",
+                    status.error.unwrap_or_default()
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Periodically save index snapshots for the default engine and every collection.
+async fn snapshot_task(state: AppState, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for (name, engine) in all_engines(&state).await {
+            let engine = engine.read().await;
+            match tokio::task::block_in_place(|| engine.snapshot()) {
+                Ok(Some(info)) => tracing::info!(
+                    "saved index snapshot for {name}: {} vectors at write {}",
+                    info.points,
+                    info.seq
+                ),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("index snapshot for {name} failed: {e}"),
+            }
+        }
+    }
+}
+
+/// Periodically run a sampled consistency check on every engine and repair
+/// the ones that disagree with storage.
+async fn consistency_task(state: AppState, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for (name, engine) in all_engines(&state).await {
+            let check = {
+                let engine = engine.read().await;
+                tokio::task::block_in_place(|| engine.check_consistency())
+            };
+            let result = match check {
+                Ok(report) if report.is_consistent() => continue,
+                Ok(_) => {
+                    let mut engine = engine.write().await;
+                    tokio::task::block_in_place(|| engine.repair())
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("consistency check for {name} failed: {e}");
+            }
+        }
+    }
+}
+
+/// Periodically verify every engine's stored checksums, quarantining the
+/// corrupt rows.
+async fn scrub_task(state: AppState, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for (name, engine) in all_engines(&state).await {
+            let mut engine = engine.write().await;
+            match tokio::task::block_in_place(|| engine.scrub()) {
+                Ok(report) if !report.quarantined.is_empty() => tracing::warn!(
+                    "scrub of {name} quarantined {} corrupt rows",
+                    report.quarantined.len()
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("scrub of {name} failed: {e}"),
+            }
+        }
+    }
+}
+
+/// Periodically estimate every index's recall, rebuilding the ones below
+/// `rebuild_below` when it is set.
+async fn recall_task(
+    state: AppState,
+    interval: Duration,
+    params: RecallParams,
+    rebuild_below: Option<f32>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for (name, engine) in all_engines(&state).await {
+            let estimate = {
+                let engine = engine.read().await;
+                tokio::task::block_in_place(|| engine.estimate_recall(&params))
+            };
+            let report = match estimate {
+                Ok(report) => report,
+                Err(e) => {
+                    tracing::warn!("recall estimate for {name} failed: {e}");
+                    continue;
+                }
+            };
+            if report.is_degraded() {
+                tracing::warn!("index {name} recall@{} is {:.3}", report.k, report.recall);
+            }
+            if rebuild_below.is_some_and(|threshold| report.recall < threshold) {
+                let mut engine = engine.write().await;
+                let rebuilt = tokio::task::block_in_place(|| {
+                    engine.rebuild_index()?;
+                    engine.estimate_recall(&params)
+                });
+                match rebuilt {
+                    Ok(after) => tracing::info!(
+                        "rebuilt index {name}: recall@{} {:.3} -> {:.3}",
+                        after.k,
+                        report.recall,
+                        after.recall
+                    ),
+                    Err(e) => tracing::warn!("rebuilding index {name} failed: {e}"),
+                }
+            }
+        }
+    }
 }
 
 async fn add_handler(
-    State(state): State<AppState>,
-    Json(payload): Json<AddRequest>,
-) -> Json<&'static str> {
-    let mut engine = state.engine.write().await;
-    let _ = engine.add_vectors(&payload.ids, &payload.vectors);
-    Json("ok")
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<AddRequest>,
+) -> Result<Json<Vec<AddResult>>, ApiError> {
+    let mut engine = engine.write().await;
+    let results =
+        engine.add_vectors_with_payloads(&payload.ids, &payload.vectors, &payload.payloads)?;
+    Ok(Json(results))
+}
+
+async fn delete_handler(
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<DeleteRequest>,
+) -> Result<Json<DeleteResponse>, ApiError> {
+    let mut engine = engine.write().await;
+    let deleted = engine.delete_vectors(&payload.ids)?;
+    Ok(Json(DeleteResponse { deleted }))
+}
+
+async fn get_vector_handler(
+    Engine(engine): Engine,
+    Path(VectorPath { id }): Path<VectorPath>,
+) -> Result<Json<StoredVector>, ApiError> {
+    let engine = engine.read().await;
+    let found = engine.get(&[id])?;
+    found
+        .into_iter()
+        .next()
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("vector_not_found", format!("vector not found: {id}")))
+}
+
+async fn get_vectors_handler(
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<GetRequest>,
+) -> Result<Json<Vec<StoredVector>>, ApiError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.get(&payload.ids)?))
 }
 
 async fn search_handler(
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<SearchRequest>,
+) -> Result<Json<Vec<SearchResult>>, ApiError> {
+    let engine = engine.read().await;
+    let params = SearchParams {
+        with_payload: payload.with_payload,
+        with_text: payload.with_text,
+        filter: payload.filter,
+    };
+    Ok(Json(engine.search_with(
+        &payload.query,
+        payload.k,
+        &params,
+    )?))
+}
+
+async fn add_text_handler(
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<AddTextRequest>,
+) -> Result<Json<Vec<AddResult>>, ApiError> {
+    // Embedding may wait on a remote service; keep it off the engine lock.
+    let embedder = engine.read().await.text_embedder();
+    let vectors = tokio::task::block_in_place(|| embedder.embed(&payload.texts))?;
+    let mut engine = engine.write().await;
+    let results =
+        engine.add_embedded_texts(&payload.ids, &payload.texts, &vectors, &payload.payloads)?;
+    Ok(Json(results))
+}
+
+async fn search_text_handler(
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<SearchTextRequest>,
+) -> Result<Json<Vec<SearchResult>>, ApiError> {
+    let embedder = engine.read().await.text_embedder();
+    let query = tokio::task::block_in_place(|| embedder.embed(&[payload.query]))?;
+    let engine = engine.read().await;
+    let params = SearchParams {
+        with_payload: payload.with_payload,
+        with_text: payload.with_text,
+        filter: payload.filter,
+    };
+    Ok(Json(engine.search_with(&query, payload.k, &params)?))
+}
+
+async fn health_handler(State(state): State<AppState>) -> Json<HealthReport> {
+    Json(state.engine.read().await.health())
+}
+
+async fn repair_handler(Engine(engine): Engine) -> Result<Json<ConsistencyReport>, ApiError> {
+    let mut engine = engine.write().await;
+    Ok(Json(tokio::task::block_in_place(|| engine.repair())?))
+}
+
+async fn scrub_handler(Engine(engine): Engine) -> Result<Json<ScrubReport>, ApiError> {
+    let mut engine = engine.write().await;
+    Ok(Json(tokio::task::block_in_place(|| engine.scrub())?))
+}
+
+/// Admin routes read and write server files. With an `admin_token` they
+/// need it as a bearer token; without one they only serve local clients.
+async fn require_admin(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    match &state.admin.token {
+        Some(token) => {
+            let bearer = request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            if bearer != Some(token.as_str()) {
+                return Err(ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "unauthorized",
+                    "admin routes need the admin token as a bearer token",
+                ));
+            }
+        }
+        None if !peer.ip().is_loopback() => {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                "admin routes only serve local clients unless an admin token is configured",
+            ));
+        }
+        None => {}
+    }
+    Ok(next.run(request).await)
+}
+
+async fn backup_handler(
     State(state): State<AppState>,
-    Json(payload): Json<SearchRequest>,
-) -> Json<serde_json::Value> {
-    let engine = state.engine.read().await;
-    let results = engine.search(&payload.query, payload.k).unwrap_or_default();
-    Json(serde_json::json!(results))
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<BackupRequest>,
+) -> Result<(StatusCode, Json<BackupManifest>), ApiError> {
+    let dest = confined_path(&state.admin.backup_dir, &payload.dest)?;
+    // Searches carry on while the backup runs; writes wait for it.
+    let engine = engine.read().await;
+    let manifest = tokio::task::block_in_place(|| engine.backup(&dest))?;
+    Ok((StatusCode::CREATED, Json(manifest)))
 }
 
-async fn health_handler(State(state): State<AppState>) -> Json<HealthResponse> {
-    let engine = state.engine.read().await;
-    let report = engine.health();
-    Json(HealthResponse {
-        ok: report.ok,
-        reason: report.reason,
-        size: report.size,
-    })
+async fn restore_backup_handler(
+    State(state): State<AppState>,
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<RestoreBackupRequest>,
+) -> Result<Json<BackupManifest>, ApiError> {
+    let source = confined_path(&state.admin.backup_dir, &payload.source)?;
+    let mut engine = engine.write().await;
+    Ok(Json(tokio::task::block_in_place(|| {
+        engine.restore_backup(&source)
+    })?))
 }
 
+async fn import_handler(
+    State(state): State<AppState>,
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<ImportRequest>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let path = confined_path(&state.admin.import_dir, &payload.path)?;
+    let mut engine = engine.write().await;
+    Ok(Json(tokio::task::block_in_place(|| {
+        engine.import_file(&path, &payload.options)
+    })?))
+}
 
+/// Stream the export as a chunked download. Writes only wait while storage
+/// is copied; the download is read from the copy.
+async fn export_handler(
+    Engine(engine): Engine,
+    query: Result<Query<ExportQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    let snapshot = {
+        let engine = engine.read().await;
+        tokio::task::block_in_place(|| engine.export_snapshot())?
+    };
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    tokio::task::spawn_blocking(move || {
+        let out = std::io::BufWriter::with_capacity(64 * 1024, ChunkWriter(tx.clone()));
+        if let Err(e) = snapshot.write(query.format, out) {
+            tracing::warn!("export failed: {e}");
+            // Ends the response with an error instead of a silently short body.
+            let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
+        }
+    });
+
+    let format = query.format;
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"vectors.{}\"", format.extension()),
+        )
+        .body(Body::from_stream(ChunkStream(rx)))
+        .expect("valid response"))
+}
+
+/// Sends written bytes to the response body; fails once the client is gone.
+struct ChunkWriter(tokio::sync::mpsc::Sender<std::io::Result<Bytes>>);
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client disconnected")
+            })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+struct ChunkStream(tokio::sync::mpsc::Receiver<std::io::Result<Bytes>>);
+
+impl futures_core::Stream for ChunkStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+async fn restore_quarantine_handler(
+    State(state): State<AppState>,
+    Engine(engine): Engine,
+    ApiJson(payload): ApiJson<RestoreQuarantineRequest>,
+) -> Result<Json<QuarantineRestoreReport>, ApiError> {
+    let source = confined_path(&state.admin.backup_dir, &payload.source)?;
+    let mut engine = engine.write().await;
+    Ok(Json(tokio::task::block_in_place(|| {
+        engine.restore_quarantined(&source)
+    })?))
+}
+
+async fn list_collections_handler(State(state): State<AppState>) -> Json<Vec<CollectionInfo>> {
+    let collections = state.collections.read().await;
+    let mut infos = Vec::new();
+    for (name, config) in collections.catalog.list() {
+        if let Some(engine) = collections.engines.get(name) {
+            infos.push(CollectionInfo {
+                name: name.to_string(),
+                config: config.clone(),
+                health: engine.read().await.health(),
+            });
+        }
+    }
+    Json(infos)
+}
+
+/// The embedder for a collection of dimension `dim`: the configured one,
+/// unless it produces vectors of another size and so cannot serve the text
+/// routes of that collection.
+fn embedder_for(configured: &Option<SharedEmbedder>, dim: usize) -> Option<SharedEmbedder> {
+    match configured {
+        None => Some(Arc::new(DummyEmbedder { dim })),
+        Some(embedder) if embedder.dim().is_some_and(|got| got != dim) => None,
+        Some(embedder) => Some(embedder.clone()),
+    }
+}
+
+async fn create_collection_handler(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateCollectionRequest>,
+) -> Result<(StatusCode, Json<CollectionInfo>), ApiError> {
+    let mut collections = state.collections.write().await;
+    let embedder = embedder_for(&state.embedder, payload.config.dim);
+    let engine = collections
+        .catalog
+        .create(&payload.name, payload.config.clone(), embedder)?;
+
+    let info = CollectionInfo {
+        name: payload.name.clone(),
+        config: payload.config,
+        health: engine.health(),
+    };
+    collections
+        .engines
+        .insert(payload.name, Arc::new(RwLock::new(engine)));
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+async fn describe_collection_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<CollectionInfo>, ApiError> {
+    let collections = state.collections.read().await;
+    let (Some(config), Some(engine)) = (
+        collections.catalog.get(&name),
+        collections.engines.get(&name),
+    ) else {
+        return Err(CollectionError::NotFound(name).into());
+    };
+    let health = engine.read().await.health();
+    Ok(Json(CollectionInfo {
+        name,
+        config: config.clone(),
+        health,
+    }))
+}
+
+async fn drop_collection_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut collections = state.collections.write().await;
+    collections.catalog.drop_collection(&name)?;
+    collections.engines.remove(&name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn start_rebuild_handler(
+    Engine(engine): Engine,
+    ApiJson(params): ApiJson<RebuildParams>,
+) -> Result<(StatusCode, Json<RebuildStatus>), ApiError> {
+    let status = engine.write().await.start_rebuild(&params)?;
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+async fn rebuild_status_handler(Engine(engine): Engine) -> Json<RebuildStatus> {
+    Json(engine.read().await.rebuild_status())
+}
@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{reject, EngineError};
+use crate::index::IndexConfig;
+use crate::segments::SegmentedIndex;
+use crate::storage::{SqliteVectorStore, StorageConfig, StorageError};
+
+/// Stored vectors read per SQLite transaction by a background build.
+const PAGE: usize = 1_000;
+
+/// `meta` key of the `RebuiltParams` recorded by the last rebuild.
+const PARAMS_KEY: &str = "rebuilt_index_params";
+
+/// HNSW parameters to change in a background rebuild; unset fields keep
+/// their current values. Dimension and metric are fixed by the stored data.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RebuildParams {
+    pub hnsw_max_elements: Option<usize>,
+    pub mutable_segment_capacity: Option<usize>,
+    pub hnsw_m: Option<usize>,
+    pub hnsw_ef_construction: Option<usize>,
+    pub hnsw_ef_search: Option<usize>,
+}
+
+impl RebuildParams {
+    /// `current` with these overrides applied.
+    pub(crate) fn apply(&self, current: &IndexConfig) -> Result<IndexConfig, EngineError> {
+        let cfg = IndexConfig {
+            max_elements: self.hnsw_max_elements.unwrap_or(current.max_elements),
+            mutable_segment_capacity: self
+                .mutable_segment_capacity
+                .unwrap_or(current.mutable_segment_capacity),
+            m: self.hnsw_m.unwrap_or(current.m),
+            ef_construction: self.hnsw_ef_construction.unwrap_or(current.ef_construction),
+            ef_search: self.hnsw_ef_search.unwrap_or(current.ef_search),
+            ..current.clone()
+        };
+        reject(cfg.problems())?;
+        Ok(cfg)
+    }
+}
+
+/// Index parameters a background rebuild switched to, with the configured
+/// ones they replaced. A restart keeps using them until the configuration
+/// itself changes.
+#[derive(Serialize, Deserialize)]
+struct RebuiltParams {
+    configured: IndexConfig,
+    built: IndexConfig,
+}
+
+/// The configuration to open the index in `store` with: the parameters of
+/// the last rebuild while `configured` is still what they replaced,
+/// otherwise `configured`.
+pub(crate) fn effective_config(
+    store: &SqliteVectorStore,
+    configured: &IndexConfig,
+) -> Result<IndexConfig, StorageError> {
+    let Some(json) = store.meta(PARAMS_KEY)? else {
+        return Ok(configured.clone());
+    };
+    match serde_json::from_str::<RebuiltParams>(&json) {
+        Ok(rebuilt) if rebuilt.configured == *configured => return Ok(rebuilt.built),
+        Ok(_) => {
+            tracing::info!("index parameters changed in the configuration since the last rebuild")
+        }
+        Err(e) => tracing::warn!("ignoring unreadable rebuilt index parameters: {e}"),
+    }
+    // Superseded, so a later return to the old configuration does not
+    // bring the old rebuild's parameters back.
+    record_params(store, configured, configured)?;
+    Ok(configured.clone())
+}
+
+/// Record that the index opened under `configured` was rebuilt with `built`.
+pub(crate) fn record_params(
+    store: &SqliteVectorStore,
+    configured: &IndexConfig,
+    built: &IndexConfig,
+) -> Result<(), StorageError> {
+    let rebuilt = RebuiltParams {
+        configured: configured.clone(),
+        built: built.clone(),
+    };
+    store.set_meta(PARAMS_KEY, &serde_json::to_string(&rebuilt)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildState {
+    /// No rebuild has run since the engine was opened.
+    Idle,
+    /// A new index is being built, or waits to replace the live one.
+    Running,
+    /// The last rebuild replaced the live index.
+    Completed,
+    /// The last rebuild failed; the live index was kept.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RebuildStatus {
+    pub state: RebuildState,
+    /// Vectors inserted into the new index so far.
+    pub inserted: usize,
+    /// Vectors the new index is built from.
+    pub total: usize,
+    /// Writes made during the build, to be replayed onto the new index.
+    pub pending_writes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RebuildStatus {
+    pub(crate) fn idle() -> Self {
+        Self {
+            state: RebuildState::Idle,
+            inserted: 0,
+            total: 0,
+            pending_writes: 0,
+            error: None,
+        }
+    }
+}
+
+/// A write made while a rebuild is running.
+pub(crate) enum DeltaOp {
+    Upsert(usize, Vec<f32>),
+    Delete(usize),
+}
+
+#[derive(Default)]
+struct Progress {
+    inserted: AtomicUsize,
+    total: AtomicUsize,
+}
+
+/// A new index being built from storage on its own thread.
+pub(crate) struct RebuildJob {
+    pub(crate) cfg: IndexConfig,
+    progress: Arc<Progress>,
+    handle: JoinHandle<Result<SegmentedIndex, EngineError>>,
+    /// Writes made since the build started, replayed before the swap.
+    pub(crate) delta: Vec<DeltaOp>,
+}
+
+impl RebuildJob {
+    /// Start building an index with `cfg` from the store at `storage`.
+    ///
+    /// The build reads through its own read-only SQLite connection, a page
+    /// per transaction, so the engine's writes never wait on more than one
+    /// page and go on to the delta buffer.
+    pub(crate) fn start(storage: StorageConfig, cfg: IndexConfig) -> Self {
+        let progress = Arc::new(Progress::default());
+        let handle = {
+            let progress = progress.clone();
+            let cfg = cfg.clone();
+            std::thread::spawn(move || {
+                let store = SqliteVectorStore::open_read_only(&storage)?;
+                progress
+                    .total
+                    .store(store.count_loadable()?, Ordering::Relaxed);
+
+                // Rows with larger ids were written during the build and
+                // are replayed from the delta; reading them would chase
+                // writers indefinitely.
+                let end = store.max_id()?;
+
+                let mut index = SegmentedIndex::new(&cfg)?;
+                let mut after = None;
+                while after < end {
+                    let rows = store.rows_after(after, PAGE)?;
+                    let Some(last) = rows.last() else { break };
+                    after = Some(last.id);
+                    for row in rows.into_iter().filter(|row| Some(row.id) <= end) {
+                        index.insert_bulk(row.id as usize, row.vector)?;
+                        progress.inserted.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                // Writes during the build may have changed the count.
+                let inserted = progress.inserted.load(Ordering::Relaxed);
+                progress.total.store(inserted, Ordering::Relaxed);
+                Ok(index)
+            })
+        };
+        Self {
+            cfg,
+            progress,
+            handle,
+            delta: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub(crate) fn status(&self) -> RebuildStatus {
+        RebuildStatus {
+            state: RebuildState::Running,
+            inserted: self.progress.inserted.load(Ordering::Relaxed),
+            total: self.progress.total.load(Ordering::Relaxed),
+            pending_writes: self.delta.len(),
+            error: None,
+        }
+    }
+
+    /// Wait for the build thread and return the new index with the writes
+    /// made during the build applied.
+    pub(crate) fn join(self) -> Result<SegmentedIndex, EngineError> {
+        let mut index = self
+            .handle
+            .join()
+            .map_err(|_| EngineError::RebuildFailed("index build panicked".to_string()))??;
+        for op in self.delta {
+            match op {
+                DeltaOp::Upsert(id, vector) => {
+                    index.insert(id, vector)?;
+                }
+                DeltaOp::Delete(id) => {
+                    index.delete(id);
+                }
+            }
+        }
+        Ok(index)
+    }
+}
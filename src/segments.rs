@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+use std::thread::JoinHandle;
+
+#[cfg(any(test, feature = "fault-injection"))]
+use crate::faults::SharedFaults;
+use crate::index::{HnswIndex, IndexConfig, IndexError, Metric};
+
+/// A sealed segment is compacted once more than 1/`STALE_FRACTION` of its
+/// slots are dead, as every search of it also walks the dead slots.
+const STALE_FRACTION: usize = 4;
+
+/// One HNSW graph holding part of the vectors.
+pub struct Segment {
+    /// Unique within a `SegmentedIndex`; also names the segment's snapshot files.
+    pub id: u64,
+    pub index: HnswIndex,
+}
+
+/// Vectors organised as sealed segments plus one mutable segment.
+///
+/// Inserts and updates go to the small mutable segment, which is sealed once
+/// it has used `mutable_segment_capacity` slots. Sealed segments only ever lose vectors:
+/// deleting or updating an id marks its old slot dead. A search fans out to
+/// every segment and merges their top-k. Compaction (`compaction_plan`,
+/// `replace_segments`) merges small sealed segments into one of up to
+/// `max_elements` slots and drops the dead slots, so no operation has to
+/// rebuild everything.
+pub struct SegmentedIndex {
+    cfg: IndexConfig,
+    sealed: Vec<Segment>,
+    mutable: Segment,
+    /// External id -> the segment holding its live vector.
+    owner: HashMap<usize, u64>,
+    next_id: u64,
+    #[cfg(any(test, feature = "fault-injection"))]
+    faults: Option<SharedFaults>,
+}
+
+impl SegmentedIndex {
+    /// An empty index; `cfg.max_elements` is the capacity of each sealed
+    /// segment.
+    pub fn new(cfg: &IndexConfig) -> Result<Self, IndexError> {
+        Ok(Self {
+            cfg: cfg.clone(),
+            sealed: Vec::new(),
+            mutable: Segment {
+                id: 0,
+                index: HnswIndex::new(&mutable_config(cfg))?,
+            },
+            owner: HashMap::new(),
+            next_id: 1,
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
+        })
+    }
+
+    /// Reassemble an index from segments loaded from a snapshot.
+    ///
+    /// A fresh mutable segment is started when `mutable` is `None`.
+    pub fn from_segments(
+        cfg: &IndexConfig,
+        sealed: Vec<Segment>,
+        mutable: Option<Segment>,
+    ) -> Result<Self, IndexError> {
+        let next_id = sealed
+            .iter()
+            .chain(&mutable)
+            .map(|s| s.id + 1)
+            .max()
+            .unwrap_or(0);
+        let (mutable, next_id) = match mutable {
+            Some(segment) => (segment, next_id),
+            None => (
+                Segment {
+                    id: next_id,
+                    index: HnswIndex::new(&mutable_config(cfg))?,
+                },
+                next_id + 1,
+            ),
+        };
+
+        let mut owner = HashMap::new();
+        for segment in sealed.iter().chain([&mutable]) {
+            for id in segment.index.ids() {
+                if owner.insert(id, segment.id).is_some() {
+                    return Err(IndexError::Snapshot(format!(
+                        "id {id} is live in more than one segment"
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            cfg: cfg.clone(),
+            sealed,
+            mutable,
+            owner,
+            next_id,
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
+        })
+    }
+
+    /// Inject failures into every segment, including later ones.
+    #[cfg(any(test, feature = "fault-injection"))]
+    pub fn set_faults(&mut self, faults: Option<SharedFaults>) {
+        for segment in self.sealed.iter_mut().chain([&mut self.mutable]) {
+            segment.index.set_faults(faults.clone());
+        }
+        self.faults = faults;
+    }
+
+    pub fn config(&self) -> &IndexConfig {
+        &self.cfg
+    }
+
+    pub fn dim(&self) -> usize {
+        self.cfg.dim
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.cfg.metric
+    }
+
+    /// Number of live vectors across all segments.
+    pub fn len(&self) -> usize {
+        self.owner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.owner.is_empty()
+    }
+
+    /// Slots used across all segments, including dead ones.
+    pub fn slot_count(&self) -> usize {
+        self.segments().map(|s| s.index.slot_count()).sum()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.sealed.len() + 1
+    }
+
+    /// Sealed segments first, then the mutable one.
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.sealed.iter().chain([&self.mutable])
+    }
+
+    /// Slots the mutable segment takes before it is sealed.
+    pub fn mutable_capacity(&self) -> usize {
+        mutable_config(&self.cfg).max_elements
+    }
+
+    pub fn mutable_segment(&self) -> &Segment {
+        &self.mutable
+    }
+
+    /// Insert or replace the vector for `id`.
+    ///
+    /// Returns `true` if an existing live vector for `id` was replaced.
+    pub fn insert(&mut self, id: usize, vector: Vec<f32>) -> Result<bool, IndexError> {
+        self.insert_sealing_at(id, vector, self.mutable_capacity())
+    }
+
+    /// Like `insert`, but fill the mutable segment up to `max_elements`
+    /// before sealing it, so building a whole index from storage yields
+    /// full segments instead of many small ones for compaction to merge.
+    pub fn insert_bulk(&mut self, id: usize, vector: Vec<f32>) -> Result<bool, IndexError> {
+        self.insert_sealing_at(id, vector, self.cfg.max_elements)
+    }
+
+    fn insert_sealing_at(
+        &mut self,
+        id: usize,
+        vector: Vec<f32>,
+        capacity: usize,
+    ) -> Result<bool, IndexError> {
+        if vector.len() != self.cfg.dim {
+            return Err(IndexError::DimMismatch {
+                expected: self.cfg.dim,
+                got: vector.len(),
+            });
+        }
+        if self.mutable.index.slot_count() >= capacity {
+            self.seal()?;
+        }
+
+        let replaced = match self.owner.get(&id) {
+            Some(&segment) if segment != self.mutable.id => {
+                self.segment(segment).index.delete(id);
+                true
+            }
+            Some(_) => true,
+            None => false,
+        };
+        self.mutable.index.insert(id, vector)?;
+        self.owner.insert(id, self.mutable.id);
+        Ok(replaced)
+    }
+
+    /// Mark `id` as deleted so it is no longer returned by `search`.
+    ///
+    /// Returns `false` if the id was not live in the index.
+    pub fn delete(&mut self, id: usize) -> bool {
+        match self.owner.remove(&id) {
+            Some(segment) => self.segment(segment).index.delete(id),
+            None => false,
+        }
+    }
+
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(usize, f32)>, IndexError> {
+        self.search_filtered(query, k, |_| true)
+    }
+
+    /// Search every segment for its `k` nearest live points whose id passes
+    /// `allow`, and merge them into the overall top `k`.
+    pub fn search_filtered<F>(
+        &self,
+        query: &[f32],
+        k: usize,
+        allow: F,
+    ) -> Result<Vec<(usize, f32)>, IndexError>
+    where
+        F: Fn(usize) -> bool,
+    {
+        if query.len() != self.cfg.dim {
+            return Err(IndexError::DimMismatch {
+                expected: self.cfg.dim,
+                got: query.len(),
+            });
+        }
+
+        let mut merged = Vec::new();
+        for segment in self.segments() {
+            merged.extend(segment.index.search_filtered(query, k, &allow)?);
+        }
+        // Reported distances sort closest-first for every metric.
+        merged.sort_by(|a, b| a.1.total_cmp(&b.1));
+        merged.truncate(k);
+        Ok(merged)
+    }
+
+    /// External ids of every live vector, in no particular order.
+    pub fn ids(&self) -> Vec<usize> {
+        self.owner.keys().copied().collect()
+    }
+
+    /// Indexed vectors for the live ids among `ids`.
+    pub fn vectors(&self, ids: &[usize]) -> HashMap<usize, Vec<f32>> {
+        let mut by_segment: HashMap<u64, Vec<usize>> = HashMap::new();
+        for id in ids {
+            if let Some(&segment) = self.owner.get(id) {
+                by_segment.entry(segment).or_default().push(*id);
+            }
+        }
+        by_segment
+            .into_iter()
+            .flat_map(|(segment, ids)| self.segment(segment).index.vectors(&ids))
+            .collect()
+    }
+
+    /// The `k` nearest of the live vectors among `ids`, scored exactly.
+    pub fn exact_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ids: &[usize],
+    ) -> Result<Vec<(usize, f32)>, IndexError> {
+        if query.len() != self.cfg.dim {
+            return Err(IndexError::DimMismatch {
+                expected: self.cfg.dim,
+                got: query.len(),
+            });
+        }
+
+        let metric = self.cfg.metric;
+        let mut scored: Vec<(usize, f32)> = self
+            .vectors(ids)
+            .into_iter()
+            .map(|(id, vector)| (id, metric.graph_distance(query, &vector)))
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.truncate(k);
+        for (_, d) in &mut scored {
+            *d = metric.reported(*d);
+        }
+        Ok(scored)
+    }
+
+    /// Sealed segments worth merging, smallest first: those less than half
+    /// full of live vectors or with more than 1/`STALE_FRACTION` of their
+    /// slots dead, as many as fit into one segment together.
+    ///
+    /// Returns an empty plan when merging would not reclaim anything.
+    pub fn compaction_plan(&self) -> Vec<u64> {
+        let capacity = self.cfg.max_elements;
+        let mut small: Vec<&Segment> = self
+            .sealed
+            .iter()
+            .filter(|s| {
+                let dead = s.index.slot_count() - s.index.len();
+                s.index.len() * 2 < capacity || dead * STALE_FRACTION > s.index.slot_count()
+            })
+            .collect();
+        small.sort_by_key(|s| s.index.len());
+
+        let mut plan = Vec::new();
+        let mut live = 0;
+        let mut dead = 0;
+        for segment in small {
+            if live + segment.index.len() > capacity {
+                break;
+            }
+            live += segment.index.len();
+            dead += segment.index.slot_count() - segment.index.len();
+            plan.push(segment.id);
+        }
+        if plan.len() < 2 && dead == 0 {
+            plan.clear();
+        }
+        plan
+    }
+
+    /// Live ids and flattened vectors of the given segments.
+    pub fn live_vectors(&self, segments: &[u64]) -> (Vec<usize>, Vec<f32>) {
+        let mut ids = Vec::new();
+        let mut flat = Vec::new();
+        for &segment in segments {
+            let index = &self.segment(segment).index;
+            for (id, vector) in index.vectors(&index.ids()) {
+                ids.push(id);
+                flat.extend(vector);
+            }
+        }
+        (ids, flat)
+    }
+
+    /// Replace the `sources` segments with `merged`, built from their live
+    /// vectors `ids` (see `live_vectors`).
+    ///
+    /// Ids deleted or updated since then are dropped from `merged`. Returns
+    /// `false` and changes nothing if a source segment no longer exists,
+    /// e.g. because the whole index was rebuilt meanwhile.
+    pub fn replace_segments(&mut self, sources: &[u64], merged: HnswIndex, ids: &[usize]) -> bool {
+        if !sources
+            .iter()
+            .all(|id| self.sealed.iter().any(|s| s.id == *id))
+        {
+            return false;
+        }
+
+        let merged_id = self.next_id;
+        self.next_id += 1;
+        for &id in ids {
+            match self.owner.get_mut(&id) {
+                Some(owner) if sources.contains(owner) => *owner = merged_id,
+                _ => {
+                    merged.delete(id);
+                }
+            }
+        }
+
+        self.sealed.retain(|s| !sources.contains(&s.id));
+        if !merged.is_empty() {
+            self.sealed.push(Segment {
+                id: merged_id,
+                index: merged,
+            });
+        }
+        true
+    }
+
+    /// Renumber this index's segments to follow those of `previous`, which it
+    /// replaces, so snapshot files of the two never share a segment id.
+    pub fn renumber_after(&mut self, previous: &SegmentedIndex) {
+        let offset = previous.next_id;
+        for segment in self.sealed.iter_mut().chain([&mut self.mutable]) {
+            segment.id += offset;
+        }
+        for segment in self.owner.values_mut() {
+            *segment += offset;
+        }
+        self.next_id += offset;
+    }
+
+    fn seal(&mut self) -> Result<(), IndexError> {
+        #[cfg_attr(not(any(test, feature = "fault-injection")), allow(unused_mut))]
+        let mut fresh = Segment {
+            id: self.next_id,
+            index: HnswIndex::new(&mutable_config(&self.cfg))?,
+        };
+        #[cfg(any(test, feature = "fault-injection"))]
+        fresh.index.set_faults(self.faults.clone());
+        self.next_id += 1;
+        self.sealed
+            .push(std::mem::replace(&mut self.mutable, fresh));
+        Ok(())
+    }
+
+    fn segment(&self, id: u64) -> &Segment {
+        self.segments()
+            .find(|s| s.id == id)
+            .expect("owner map only refers to existing segments")
+    }
+}
+
+/// `cfg` sized for a mutable segment.
+fn mutable_config(cfg: &IndexConfig) -> IndexConfig {
+    IndexConfig {
+        max_elements: cfg
+            .mutable_segment_capacity
+            .clamp(1, cfg.max_elements.max(1)),
+        ..cfg.clone()
+    }
+}
+
+/// Sealed segments being merged on a background thread.
+pub(crate) struct CompactionJob {
+    sources: Vec<u64>,
+    ids: Vec<usize>,
+    handle: JoinHandle<Result<HnswIndex, IndexError>>,
+}
+
+impl CompactionJob {
+    /// Start merging the segments in `plan` (see `compaction_plan`).
+    pub(crate) fn start(index: &SegmentedIndex, plan: Vec<u64>) -> Self {
+        let (ids, flat) = index.live_vectors(&plan);
+        let cfg = index.config().clone();
+        let handle = {
+            let ids = ids.clone();
+            std::thread::spawn(move || {
+                let merged = HnswIndex::new(&cfg)?;
+                for (i, chunk) in flat.chunks(cfg.dim).enumerate() {
+                    merged.insert(ids[i], chunk.to_vec())?;
+                }
+                Ok(merged)
+            })
+        };
+        Self {
+            sources: plan,
+            ids,
+            handle,
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the merge and swap the merged segment into `index`.
+    ///
+    /// Returns `false` if the sources were gone and the result was discarded.
+    pub(crate) fn finish(self, index: &mut SegmentedIndex) -> Result<bool, IndexError> {
+        let merged = self
+            .handle
+            .join()
+            .map_err(|_| IndexError::Snapshot("segment merge panicked".to_string()))??;
+        Ok(index.replace_segments(&self.sources, merged, &self.ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_elements: usize) -> IndexConfig {
+        IndexConfig {
+            dim: 2,
+            metric: Metric::L2,
+            max_elements,
+            mutable_segment_capacity: max_elements,
+            m: 8,
+            ef_construction: 16,
+            ef_search: 16,
+        }
+    }
+
+    #[test]
+    fn full_segments_are_sealed_and_searched_together() {
+        let mut index = SegmentedIndex::new(&config(4)).expect("index created");
+        for id in 0..10 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+        assert_eq!(index.segment_count(), 3);
+        assert_eq!(index.len(), 10);
+
+        let neighbors = index.search(&[7.2, 0.0], 3).unwrap();
+        let ids: Vec<usize> = neighbors.iter().map(|n| n.0).collect();
+        assert_eq!(ids, vec![7, 8, 6]);
+
+        // Updating an id in a sealed segment moves it to the mutable one.
+        assert!(index.insert(1, vec![100.0, 0.0]).unwrap());
+        assert_eq!(index.len(), 10);
+        assert_eq!(index.search(&[100.0, 0.0], 1).unwrap()[0].0, 1);
+        assert!(index
+            .search(&[1.0, 0.0], 2)
+            .unwrap()
+            .iter()
+            .all(|n| n.0 != 1));
+
+        assert!(index.delete(8));
+        assert!(!index.delete(8));
+        assert!(index
+            .search(&[8.0, 0.0], 3)
+            .unwrap()
+            .iter()
+            .all(|n| n.0 != 8));
+    }
+
+    #[test]
+    fn compaction_merges_small_segments_and_drops_dead_slots() {
+        let mut index = SegmentedIndex::new(&config(4)).expect("index created");
+        for id in 0..12 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+        // Two sealed segments, each down to one live vector.
+        for id in [0, 1, 2, 4, 5, 6] {
+            index.delete(id);
+        }
+        let plan = index.compaction_plan();
+        assert_eq!(plan.len(), 2);
+
+        let job = CompactionJob::start(&index, plan);
+        // Changes made while the merge runs win over the merged copy.
+        index.delete(3);
+        index.insert(7, vec![70.0, 0.0]).unwrap();
+        assert!(job.finish(&mut index).unwrap());
+
+        // Both merged vectors changed meanwhile, so the merge leaves nothing.
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.slot_count(), 5);
+        assert_eq!(index.segment_count(), 2);
+        let ids: Vec<usize> = index
+            .search(&[0.0, 0.0], 10)
+            .unwrap()
+            .into_iter()
+            .map(|n| n.0)
+            .collect();
+        assert_eq!(ids, vec![8, 9, 10, 11, 7]);
+        assert!(index.compaction_plan().is_empty());
+    }
+
+    #[test]
+    fn a_full_segment_with_many_dead_slots_is_compacted_on_its_own() {
+        let mut index = SegmentedIndex::new(&config(8)).expect("index created");
+        for id in 0..10 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+        // Still more than half full, but a quarter of its slots are dead.
+        index.delete(0);
+        assert!(index.compaction_plan().is_empty());
+        index.delete(1);
+        index.insert(2, vec![20.0, 0.0]).unwrap();
+        let plan = index.compaction_plan();
+        assert_eq!(plan.len(), 1);
+
+        let job = CompactionJob::start(&index, plan);
+        assert!(job.finish(&mut index).unwrap());
+        assert_eq!(index.len(), 8);
+        assert_eq!(index.slot_count(), 8);
+        assert!(index.compaction_plan().is_empty());
+    }
+
+    #[test]
+    fn the_mutable_segment_is_sealed_early_but_bulk_loads_fill_segments() {
+        let cfg = IndexConfig {
+            mutable_segment_capacity: 2,
+            ..config(8)
+        };
+        let mut index = SegmentedIndex::new(&cfg).expect("index created");
+        for id in 0..5 {
+            index.insert(id, vec![id as f32, 0.0]).unwrap();
+        }
+        assert_eq!(index.segment_count(), 3);
+
+        let mut bulk = SegmentedIndex::new(&cfg).expect("index created");
+        for id in 0..10 {
+            bulk.insert_bulk(id, vec![id as f32, 0.0]).unwrap();
+        }
+        assert_eq!(bulk.segment_count(), 2);
+        // Later writes seal the large mutable segment right away.
+        bulk.insert(10, vec![10.0, 0.0]).unwrap();
+        assert_eq!(bulk.segment_count(), 3);
+        assert_eq!(bulk.search(&[3.1, 0.0], 1).unwrap()[0].0, 3);
+    }
+}
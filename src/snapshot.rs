@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::index::{HnswIndex, IdMapState, IndexConfig, IndexError, Metric};
+use crate::segments::{Segment, SegmentedIndex};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("index error: {0}")]
+    Index(#[from] IndexError),
+
+    #[error("corrupt snapshot: {0}")]
+    Corrupt(String),
+}
+
+/// Describes a saved snapshot; segment files are only trusted when their
+/// checksums match.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Storage write sequence number the snapshot covers.
+    seq: u64,
+    dim: usize,
+    metric: Metric,
+    segments: Vec<SegmentEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SegmentEntry {
+    id: u64,
+    mutable: bool,
+    graph_file: String,
+    graph_crc32: u32,
+    data_file: String,
+    data_crc32: u32,
+    ids: IdMapState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SnapshotInfo {
+    /// Storage write sequence number the snapshot covers.
+    pub seq: u64,
+    /// Live points in the snapshot.
+    pub points: usize,
+    pub segments: usize,
+    /// Segments whose graph had to be written; the others were kept from
+    /// the previous snapshot.
+    pub written: usize,
+}
+
+/// Snapshot directory for a SQLite file: `vectors.sqlite` -> `vectors.sqlite.snapshot/`.
+pub fn snapshot_dir(storage_path: &Path) -> PathBuf {
+    let mut name = storage_path.as_os_str().to_owned();
+    name.push(".snapshot");
+    PathBuf::from(name)
+}
+
+/// Dump `index` into `dir` as the snapshot for storage state `seq`.
+///
+/// Sealed segments get no new points, so a graph already saved for one is
+/// kept and only its id map is updated; the mutable segment is written on
+/// every snapshot. Files are written under fresh names and the manifest is
+/// replaced last, so a crash leaves either the previous snapshot or the new
+/// one in place.
+pub fn save(dir: &Path, index: &SegmentedIndex, seq: u64) -> Result<SnapshotInfo, SnapshotError> {
+    std::fs::create_dir_all(dir)?;
+    // A missing or unreadable previous manifest only means nothing is reused.
+    let previous = read_manifest(dir).ok().flatten();
+
+    let mutable_id = index.mutable_segment().id;
+    let mut segments = Vec::new();
+    let mut written = 0;
+    for segment in index.segments().filter(|s| !s.index.is_empty()) {
+        let mutable = segment.id == mutable_id;
+        let saved = previous.iter().flat_map(|m| &m.segments).find(|e| {
+            !mutable
+                && !e.mutable
+                && e.id == segment.id
+                && dir.join(&e.graph_file).exists()
+                && dir.join(&e.data_file).exists()
+        });
+        let entry = match saved {
+            Some(entry) => SegmentEntry {
+                ids: segment.index.id_map(),
+                ..entry.clone()
+            },
+            None => {
+                written += 1;
+                let name = if mutable {
+                    format!("seg-{}-{seq}", segment.id)
+                } else {
+                    format!("seg-{}", segment.id)
+                };
+                write_segment(dir, &name, segment, mutable)?
+            }
+        };
+        segments.push(entry);
+    }
+
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        seq,
+        dim: index.dim(),
+        metric: index.metric(),
+        segments,
+    };
+    let info = SnapshotInfo {
+        seq,
+        points: manifest.segments.iter().map(|e| e.ids.live.len()).sum(),
+        segments: manifest.segments.len(),
+        written,
+    };
+
+    let tmp = dir.join(format!("{MANIFEST_FILE}.tmp"));
+    let mut out = File::create(&tmp)?;
+    out.write_all(&serde_json::to_vec(&manifest)?)?;
+    out.sync_all()?;
+    std::fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+
+    // Drop files no longer referenced: earlier mutable segments and
+    // segments merged away by compaction.
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let referenced = name == MANIFEST_FILE
+            || manifest
+                .segments
+                .iter()
+                .any(|e| name == e.graph_file.as_str() || name == e.data_file.as_str());
+        if !referenced {
+            std::fs::remove_file(dir.join(name)).ok();
+        }
+    }
+    Ok(info)
+}
+
+fn write_segment(
+    dir: &Path,
+    name: &str,
+    segment: &Segment,
+    mutable: bool,
+) -> Result<SegmentEntry, SnapshotError> {
+    let graph_file = format!("{name}.hnsw.graph");
+    let data_file = format!("{name}.hnsw.data");
+    let ids = segment.index.dump(
+        File::create(dir.join(&graph_file))?,
+        File::create(dir.join(&data_file))?,
+    )?;
+    for file in [&graph_file, &data_file] {
+        File::open(dir.join(file))?.sync_all()?;
+    }
+    Ok(SegmentEntry {
+        id: segment.id,
+        mutable,
+        graph_crc32: checksum(&dir.join(&graph_file))?,
+        graph_file,
+        data_crc32: checksum(&dir.join(&data_file))?,
+        data_file,
+        ids,
+    })
+}
+
+fn read_manifest(dir: &Path) -> Result<Option<Manifest>, SnapshotError> {
+    match std::fs::read(dir.join(MANIFEST_FILE)) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Load the snapshot in `dir`, returning the index and the storage write
+/// sequence number it covers, or `None` if there is no snapshot.
+pub fn load(dir: &Path, cfg: &IndexConfig) -> Result<Option<(SegmentedIndex, u64)>, SnapshotError> {
+    let Some(manifest) = read_manifest(dir)? else {
+        return Ok(None);
+    };
+
+    if manifest.version != FORMAT_VERSION {
+        return Err(SnapshotError::Corrupt(format!(
+            "unsupported snapshot version {}",
+            manifest.version
+        )));
+    }
+    if manifest.dim != cfg.dim || manifest.metric != cfg.metric {
+        return Err(SnapshotError::Corrupt(format!(
+            "snapshot is for dim {} / {}, configured dim {} / {}",
+            manifest.dim, manifest.metric, cfg.dim, cfg.metric
+        )));
+    }
+
+    let mut sealed = Vec::new();
+    let mut mutable = None;
+    for entry in manifest.segments {
+        for (file, crc) in [
+            (&entry.graph_file, entry.graph_crc32),
+            (&entry.data_file, entry.data_crc32),
+        ] {
+            if checksum(&dir.join(file))? != crc {
+                return Err(SnapshotError::Corrupt(format!(
+                    "checksum mismatch in {file}"
+                )));
+            }
+        }
+
+        let mut graph = BufReader::new(File::open(dir.join(&entry.graph_file))?);
+        let mut data = BufReader::new(File::open(dir.join(&entry.data_file))?);
+        let segment = Segment {
+            id: entry.id,
+            index: HnswIndex::load(cfg, &mut graph, &mut data, entry.ids)?,
+        };
+        if entry.mutable {
+            mutable = Some(segment);
+        } else {
+            sealed.push(segment);
+        }
+    }
+    let index = SegmentedIndex::from_segments(cfg, sealed, mutable)?;
+    Ok(Some((index, manifest.seq)))
+}
+
+/// Delete the snapshot in `dir`, if any.
+pub fn remove(dir: &Path) -> io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn checksum(path: &Path) -> io::Result<u32> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
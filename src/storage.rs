@@ -1,13 +1,22 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, DatabaseName, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+#[cfg(any(test, feature = "fault-injection"))]
+use crate::faults::{fires, Fault, SharedFaults};
+use crate::filter::Filter;
+
 #[derive(Debug, Error)]
 pub enum StorageError {
     #[error("sqlite error: {0}")]
     Sqlite(#[from] rusqlite::Error),
+
+    #[error("invalid payload json: {0}")]
+    Payload(#[from] serde_json::Error),
 }
 
 #[derive(Debug, Clone)]
@@ -16,9 +25,59 @@ pub struct StorageConfig {
     pub dim: usize,
 }
 
+/// Stored as SQLite's `user_version`; raised whenever `init_schema` adds a
+/// table or column. Databases last opened by older versions read as 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
 pub struct SqliteVectorStore {
     conn: Mutex<Connection>,
     dim: usize,
+    #[cfg(any(test, feature = "fault-injection"))]
+    faults: Option<SharedFaults>,
+}
+
+/// A single stored row: the vector, its optional JSON payload and, for rows
+/// ingested as text, the source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredRow {
+    pub id: i64,
+    pub vector: Vec<f32>,
+    pub payload: Option<Value>,
+    pub text: Option<String>,
+}
+
+/// A write recorded in the operation log.
+///
+/// Entries name the affected ids rather than copying the vectors: the rows
+/// themselves are committed in the same transaction, so replaying a log
+/// means reading the current rows of the ids it touched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    /// Rows added or replaced.
+    Add { ids: Vec<i64> },
+    /// Rows deleted; only ids that were present are recorded.
+    Delete { ids: Vec<i64> },
+    /// A `meta` value changed.
+    SetMeta { key: String, value: String },
+    /// Corrupt rows moved from `vectors` to `quarantine`.
+    Quarantine { ids: Vec<i64> },
+}
+
+/// A row moved out of `vectors` because it failed verification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuarantinedRow {
+    pub id: i64,
+    pub reason: String,
+    /// Unix time in seconds.
+    pub quarantined_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Write sequence number of the operation; consecutive from 1.
+    pub seq: u64,
+    pub op: Op,
 }
 
 impl SqliteVectorStore {
@@ -30,66 +89,768 @@ impl SqliteVectorStore {
         let store = Self {
             conn: Mutex::new(conn),
             dim: cfg.dim,
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
         };
         store.init_schema()?;
         Ok(store)
     }
 
+    /// Open an existing store without creating or migrating anything, e.g.
+    /// a backup to restore rows from.
+    pub fn open_read_only(cfg: &StorageConfig) -> Result<Self, StorageError> {
+        let conn = Connection::open_with_flags(&cfg.path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            dim: cfg.dim,
+            #[cfg(any(test, feature = "fault-injection"))]
+            faults: None,
+        })
+    }
+
     fn init_schema(&self) -> Result<(), StorageError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "CREATE TABLE IF NOT EXISTS vectors (
                 id INTEGER PRIMARY KEY,
-                vector BLOB NOT NULL
+                vector BLOB NOT NULL,
+                payload TEXT,
+                text TEXT
             );",
             [],
         )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        // Every write, keyed by its write sequence number and committed in
+        // the same transaction as the rows it changed, so index snapshots
+        // can replay exactly the writes made after they were taken.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS oplog (
+                seq INTEGER PRIMARY KEY,
+                entry TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        // Rows that failed verification, kept as found until they are
+        // restored from a backup, overwritten or deleted.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS quarantine (
+                id INTEGER PRIMARY KEY,
+                vector BLOB,
+                payload TEXT,
+                text TEXT,
+                crc32 INTEGER,
+                reason TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            );",
+            [],
+        )?;
+
+        // Databases created by older versions lack the newer columns.
+        for (column, decl) in [("payload", "TEXT"), ("text", "TEXT"), ("crc32", "INTEGER")] {
+            let exists: bool = conn
+                .prepare("SELECT 1 FROM pragma_table_info('vectors') WHERE name = ?1;")?
+                .exists(params![column])?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE vectors ADD COLUMN {column} {decl};"),
+                    [],
+                )?;
+            }
+        }
+
+        // Rows written before checksums existed are trusted as they are.
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut select =
+                tx.prepare("SELECT id, vector, payload, text FROM vectors WHERE crc32 IS NULL;")?;
+            let mut update = tx.prepare("UPDATE vectors SET crc32 = ?2 WHERE id = ?1;")?;
+            let mut rows = select.query([])?;
+            while let Some(row) = rows.next()? {
+                let id: i64 = row.get(0)?;
+                let blob: Vec<u8> = row.get(1)?;
+                let payload: Option<String> = row.get(2)?;
+                let text: Option<String> = row.get(3)?;
+                update.execute(params![
+                    id,
+                    row_checksum(&blob, payload.as_deref(), text.as_deref())
+                ])?;
+            }
+        }
+        tx.commit()?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(())
+    }
+
+    /// Schema version the database was last migrated to; see `SCHEMA_VERSION`.
+    pub fn schema_version(&self) -> Result<u32, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Inject failures into later writes (see `Fault`).
+    #[cfg(any(test, feature = "fault-injection"))]
+    pub fn set_faults(&mut self, faults: Option<SharedFaults>) {
+        self.faults = faults;
+    }
+
+    /// `Fault::StorageWrite`: fail the `i`th of `len` writes of a
+    /// transaction halfway through it, before it commits.
+    #[cfg(any(test, feature = "fault-injection"))]
+    fn fail_halfway(&self, i: usize, len: usize) -> Result<(), StorageError> {
+        if i == len / 2 && fires(&self.faults, Fault::StorageWrite) {
+            return Err(StorageError::Sqlite(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
+                Some("injected fault".to_string()),
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(test, feature = "fault-injection")))]
+    fn fail_halfway(&self, _i: usize, _len: usize) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// `Fault::TruncateBlob` and `Fault::CorruptBlob`: damage a vector blob
+    /// after its checksum was taken.
+    #[cfg(any(test, feature = "fault-injection"))]
+    fn damage(&self, mut blob: Vec<u8>) -> Vec<u8> {
+        if fires(&self.faults, Fault::TruncateBlob) {
+            blob.pop();
+        }
+        if fires(&self.faults, Fault::CorruptBlob) {
+            blob[0] ^= 0xff;
+        }
+        blob
+    }
+
+    #[cfg(not(any(test, feature = "fault-injection")))]
+    fn damage(&self, blob: Vec<u8>) -> Vec<u8> {
+        blob
+    }
+
+    /// Read a value from the `meta` key/value table.
+    pub fn meta(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1;",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Write a value into the `meta` key/value table.
+    ///
+    /// A change is logged like any other write; setting the current value
+    /// again is not a write.
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let current: Option<String> = tx
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1;",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if current.as_deref() == Some(value) {
+            return Ok(());
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2);",
+            params![key, value],
+        )?;
+        append_log(
+            &tx,
+            &Op::SetMeta {
+                key: key.to_string(),
+                value: value.to_string(),
+            },
+        )?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn add(&self, ids: &[i64], vectors: &[f32]) -> Result<(), StorageError> {
+    /// Number of stored rows.
+    pub fn count(&self) -> Result<usize, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let n: i64 = conn.query_row("SELECT COUNT(*) FROM vectors;", [], |row| row.get(0))?;
+        Ok(n as usize)
+    }
+
+    /// Components per stored vector.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of rows with a vector of the configured dim; see `ids`.
+    pub fn count_loadable(&self) -> Result<usize, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let n: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM vectors WHERE length(vector) = ?1;",
+            params![(self.dim * std::mem::size_of::<f32>()) as i64],
+            |row| row.get(0),
+        )?;
+        Ok(n as usize)
+    }
+
+    /// Largest stored id, or `None` if the store is empty.
+    pub fn max_id(&self) -> Result<Option<i64>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row("SELECT MAX(id) FROM vectors;", [], |row| row.get(0))?)
+    }
+
+    /// Number of rows per stored vector dimension, in ascending order of
+    /// dimension. Rows of any but the configured dimension fail
+    /// verification.
+    pub fn vector_dims(&self) -> Result<Vec<(usize, usize)>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let dims = conn
+            .prepare(
+                "SELECT length(vector), COUNT(*) FROM vectors
+                 GROUP BY length(vector) ORDER BY length(vector);",
+            )?
+            .query_map([], |row| {
+                let (bytes, rows): (i64, i64) = (row.get(0)?, row.get(1)?);
+                Ok((bytes as usize / std::mem::size_of::<f32>(), rows as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dims)
+    }
+
+    /// Ids of the rows with a vector of the right length, in ascending
+    /// order. Rows that fail their checksum are included until `scrub`
+    /// quarantines them.
+    pub fn ids(&self) -> Result<Vec<i64>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let ids = conn
+            .prepare("SELECT id FROM vectors WHERE length(vector) = ?1 ORDER BY id;")?
+            .query_map(
+                params![(self.dim * std::mem::size_of::<f32>()) as i64],
+                |row| row.get(0),
+            )?
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Sequence number of the latest write; every add, delete or metadata
+    /// change increments it.
+    pub fn write_seq(&self) -> Result<u64, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        read_seq(&conn)
+    }
+
+    /// Insert or replace vectors by id, without payloads.
+    ///
+    /// Returns, for each id, whether it replaced an existing row.
+    pub fn add(&self, ids: &[i64], vectors: &[f32]) -> Result<Vec<bool>, StorageError> {
+        self.add_with_payloads(ids, vectors, &[])
+    }
+
+    /// Insert or replace vectors by id together with their JSON payloads.
+    ///
+    /// `payloads` is either empty or holds one value per id; `Value::Null`
+    /// stores no payload. Replacing a row replaces its payload too.
+    pub fn add_with_payloads(
+        &self,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+    ) -> Result<Vec<bool>, StorageError> {
+        self.add_with_texts(ids, vectors, payloads, &[])
+    }
+
+    /// Like `add_with_payloads`, but also records the source text each
+    /// vector was embedded from. `texts` is either empty or one per id.
+    pub fn add_with_texts(
+        &self,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+        texts: &[String],
+    ) -> Result<Vec<bool>, StorageError> {
         let n = vectors.len() / self.dim;
-        if n != ids.len() {
+        if n != ids.len()
+            || (!payloads.is_empty() && payloads.len() != ids.len())
+            || (!texts.is_empty() && texts.len() != ids.len())
+        {
             return Err(StorageError::Sqlite(rusqlite::Error::InvalidQuery));
         }
 
         let conn = self.conn.lock().unwrap();
         let tx = conn.unchecked_transaction()?;
-        {
-            let mut stmt = tx.prepare(
-                "INSERT OR REPLACE INTO vectors (id, vector) VALUES (?1, ?2);",
-            )?;
+        let texts: Vec<Option<&str>> = texts.iter().map(|t| Some(t.as_str())).collect();
+        let replaced = self.write_rows(&tx, ids, vectors, payloads, &texts)?;
+        append_log(&tx, &Op::Add { ids: ids.to_vec() })?;
+        tx.commit()?;
+        Ok(replaced)
+    }
+
+    /// Insert or replace rows inside the caller's transaction. A new row
+    /// for an id supersedes any quarantined one.
+    fn write_rows(
+        &self,
+        tx: &Connection,
+        ids: &[i64],
+        vectors: &[f32],
+        payloads: &[Value],
+        texts: &[Option<&str>],
+    ) -> Result<Vec<bool>, StorageError> {
+        let mut replaced = Vec::with_capacity(ids.len());
+        let mut exists = tx.prepare("SELECT 1 FROM vectors WHERE id = ?1;")?;
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO vectors (id, vector, payload, text, crc32)
+             VALUES (?1, ?2, ?3, ?4, ?5);",
+        )?;
+        let mut unquarantine = tx.prepare("DELETE FROM quarantine WHERE id = ?1;")?;
 
-            for (i, chunk) in vectors.chunks(self.dim).enumerate() {
-                stmt.execute(params![ids[i], bytemuck::cast_slice(chunk)])?;
+        for (i, chunk) in vectors.chunks(self.dim).enumerate() {
+            self.fail_halfway(i, ids.len())?;
+            let payload = match payloads.get(i) {
+                None | Some(Value::Null) => None,
+                Some(value) => Some(value.to_string()),
+            };
+            let text = texts.get(i).copied().flatten();
+            let blob: Vec<u8> = bytemuck::cast_slice(chunk).to_vec();
+            let crc = row_checksum(&blob, payload.as_deref(), text);
+            let blob = self.damage(blob);
+            replaced.push(exists.exists(params![ids[i]])?);
+            stmt.execute(params![ids[i], blob, payload, text, crc])?;
+            unquarantine.execute(params![ids[i]])?;
+        }
+        Ok(replaced)
+    }
+
+    /// Delete the given ids and return the ones that were actually present.
+    ///
+    /// Quarantined rows of these ids are dropped as well.
+    pub fn delete(&self, ids: &[i64]) -> Result<Vec<i64>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let mut deleted = Vec::with_capacity(ids.len());
+        {
+            let mut stmt = tx.prepare("DELETE FROM vectors WHERE id = ?1;")?;
+            let mut unquarantine = tx.prepare("DELETE FROM quarantine WHERE id = ?1;")?;
+            for (i, &id) in ids.iter().enumerate() {
+                self.fail_halfway(i, ids.len())?;
+                unquarantine.execute(params![id])?;
+                if stmt.execute(params![id])? > 0 {
+                    deleted.push(id);
+                }
             }
         }
+        if !deleted.is_empty() {
+            append_log(
+                &tx,
+                &Op::Delete {
+                    ids: deleted.clone(),
+                },
+            )?;
+        }
         tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Logged operations after `seq`, oldest first, for replaying onto an
+    /// older index snapshot.
+    pub fn log_since(&self, seq: u64) -> Result<Vec<LogEntry>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT seq, entry FROM oplog WHERE seq > ?1 ORDER BY seq;")?;
+        let mut rows = stmt.query(params![seq as i64])?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let seq: i64 = row.get(0)?;
+            let entry: String = row.get(1)?;
+            entries.push(LogEntry {
+                seq: seq as u64,
+                op: serde_json::from_str(&entry)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Drop log entries at or below `seq` once nothing needs to replay them.
+    pub fn truncate_log(&self, seq: u64) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM oplog WHERE seq <= ?1;", params![seq as i64])?;
         Ok(())
     }
 
-    pub fn load_all(&self) -> Result<(Vec<i64>, Vec<f32>), StorageError> {
+    /// Fetch rows by id. Ids that are not stored are omitted.
+    pub fn get(&self, ids: &[i64]) -> Result<Vec<StoredRow>, StorageError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT id, vector FROM vectors;")?;
+        let mut stmt =
+            conn.prepare("SELECT vector, payload, text, crc32 FROM vectors WHERE id = ?1;")?;
+
+        let mut found = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let mut rows = stmt.query(params![id])?;
+            if let Some(row) = rows.next()? {
+                found.extend(self.stored_row(id, row)?);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Up to `limit` rows with ids above `after` (all ids if `None`), in
+    /// ascending id order, for paging through the whole store without
+    /// loading it at once.
+    pub fn rows_after(
+        &self,
+        after: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<StoredRow>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT vector, payload, text, crc32, id FROM vectors
+             WHERE ?1 IS NULL OR id > ?1 ORDER BY id LIMIT ?2;",
+        )?;
+        let mut rows = stmt.query(params![after, limit as i64])?;
+
+        let mut found = Vec::with_capacity(limit);
+        while let Some(row) = rows.next()? {
+            found.extend(self.stored_row(row.get(4)?, row)?);
+        }
+        Ok(found)
+    }
+
+    /// Decode a row selected as `vector, payload, text, crc32, ...`; `None`
+    /// if it fails verification.
+    fn stored_row(
+        &self,
+        id: i64,
+        row: &rusqlite::Row<'_>,
+    ) -> Result<Option<StoredRow>, StorageError> {
+        let blob: Vec<u8> = row.get(0)?;
+        let payload: Option<String> = row.get(1)?;
+        let text: Option<String> = row.get(2)?;
+        let vector = match self.verify(&blob, payload.as_deref(), text.as_deref(), row.get(3)?) {
+            Ok(vector) => vector,
+            Err(reason) => {
+                // Left for `scrub` to quarantine; reads have no side effects.
+                tracing::warn!("skipping corrupt row {id}: {reason}");
+                return Ok(None);
+            }
+        };
+        Ok(Some(StoredRow {
+            id,
+            vector,
+            payload: payload.map(|p| serde_json::from_str(&p)).transpose()?,
+            text,
+        }))
+    }
+
+    /// Fetch the JSON payload for a single id, if it has one.
+    pub fn payload(&self, id: i64) -> Result<Option<Value>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let payload: Option<String> = conn
+            .query_row(
+                "SELECT payload FROM vectors WHERE id = ?1;",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        Ok(payload.map(|p| serde_json::from_str(&p)).transpose()?)
+    }
+
+    /// Fetch the source text for a single id, if it was ingested as text.
+    pub fn text(&self, id: i64) -> Result<Option<String>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT text FROM vectors WHERE id = ?1;",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Ids of the rows whose payload satisfies `filter`.
+    pub fn filter_ids(&self, filter: &Filter) -> Result<Vec<i64>, StorageError> {
+        let mut params = Vec::new();
+        let predicate = filter.to_sql(&mut params);
+        let sql = format!("SELECT id FROM vectors WHERE {predicate};");
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let ids = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// Ids and flattened vectors of every row that passes verification,
+    /// and the ids of the rows that fail with the reason.
+    fn scan(&self, conn: &Connection) -> Result<Scan, StorageError> {
+        let mut stmt = conn.prepare("SELECT id, vector, payload, text, crc32 FROM vectors;")?;
         let mut rows = stmt.query([])?;
 
         let mut ids = Vec::new();
         let mut all_vecs = Vec::new();
+        let mut corrupt = Vec::new();
 
         while let Some(row) = rows.next()? {
             let id: i64 = row.get(0)?;
             let blob: Vec<u8> = row.get(1)?;
-            let floats: &[f32] = bytemuck::cast_slice(&blob);
-            if floats.len() != self.dim {
-                // skip malformed rows for now
-                continue;
+            let payload: Option<String> = row.get(2)?;
+            let text: Option<String> = row.get(3)?;
+            match self.verify(&blob, payload.as_deref(), text.as_deref(), row.get(4)?) {
+                Ok(floats) => {
+                    ids.push(id);
+                    all_vecs.extend(floats);
+                }
+                Err(reason) => corrupt.push((id, reason)),
             }
-            ids.push(id);
-            all_vecs.extend_from_slice(floats);
         }
+        Ok((ids, all_vecs, corrupt))
+    }
 
+    /// Ids and flattened vectors of every row that passes verification.
+    ///
+    /// Corrupt rows are skipped; `scrub` moves them to quarantine.
+    pub fn load_all(&self) -> Result<(Vec<i64>, Vec<f32>), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let (ids, all_vecs, corrupt) = self.scan(&conn)?;
+        if !corrupt.is_empty() {
+            tracing::warn!(
+                "skipped {} corrupt rows while loading vectors",
+                corrupt.len()
+            );
+        }
         Ok((ids, all_vecs))
     }
+
+    /// Verify every row's length and checksum and move the rows that fail
+    /// to the `quarantine` table.
+    ///
+    /// Returns the number of rows checked and the ids quarantined.
+    pub fn scrub(&self) -> Result<(usize, Vec<i64>), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let (ids, _, corrupt) = self.scan(&conn)?;
+        let checked = ids.len() + corrupt.len();
+        if corrupt.is_empty() {
+            return Ok((checked, Vec::new()));
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut copy = tx.prepare(
+                "INSERT OR REPLACE INTO quarantine
+                    (id, vector, payload, text, crc32, reason, quarantined_at)
+                 SELECT id, vector, payload, text, crc32, ?2, ?3 FROM vectors WHERE id = ?1;",
+            )?;
+            let mut remove = tx.prepare("DELETE FROM vectors WHERE id = ?1;")?;
+            for (id, reason) in &corrupt {
+                tracing::warn!("quarantining corrupt row {id}: {reason}");
+                copy.execute(params![id, reason, now])?;
+                remove.execute(params![id])?;
+            }
+        }
+        let ids: Vec<i64> = corrupt.into_iter().map(|(id, _)| id).collect();
+        append_log(&tx, &Op::Quarantine { ids: ids.clone() })?;
+        tx.commit()?;
+        Ok((checked, ids))
+    }
+
+    /// Verify every row like `scrub`, without changing anything.
+    ///
+    /// Returns the number of rows checked and the ids that fail.
+    pub fn verify_rows(&self) -> Result<(usize, Vec<i64>), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let (ids, _, corrupt) = self.scan(&conn)?;
+        Ok((
+            ids.len() + corrupt.len(),
+            corrupt.into_iter().map(|(id, _)| id).collect(),
+        ))
+    }
+
+    /// Problems found by SQLite's own integrity check; empty if none.
+    pub fn integrity_errors(&self) -> Result<Vec<String>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let messages = conn
+            .prepare("PRAGMA integrity_check;")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages.into_iter().filter(|m| m != "ok").collect())
+    }
+
+    /// Copy the database to `dest` with SQLite's online backup API; the copy
+    /// is a consistent image of one point in time.
+    pub fn backup_to(&self, dest: &Path) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        conn.backup(DatabaseName::Main, dest, None)?;
+        Ok(())
+    }
+
+    /// Replace the whole database with the one at `src` (e.g. a backup),
+    /// then bring its schema up to date.
+    pub fn restore_from(&self, src: &Path) -> Result<(), StorageError> {
+        self.conn.lock().unwrap().restore(
+            DatabaseName::Main,
+            src,
+            None::<fn(rusqlite::backup::Progress)>,
+        )?;
+        self.init_schema()
+    }
+
+    /// Number of rows in quarantine.
+    pub fn quarantine_count(&self) -> Result<usize, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let n: i64 = conn.query_row("SELECT COUNT(*) FROM quarantine;", [], |row| row.get(0))?;
+        Ok(n as usize)
+    }
+
+    /// Quarantined rows, by id.
+    pub fn quarantined(&self) -> Result<Vec<QuarantinedRow>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn
+            .prepare("SELECT id, reason, quarantined_at FROM quarantine ORDER BY id;")?
+            .query_map([], |row| {
+                Ok(QuarantinedRow {
+                    id: row.get(0)?,
+                    reason: row.get(1)?,
+                    quarantined_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Replace quarantined rows with the intact copies held by `source`
+    /// (a backup or replica of this store), and return the ids restored.
+    ///
+    /// Rows `source` lacks or holds corrupted too stay in quarantine.
+    pub fn restore_quarantined(
+        &self,
+        source: &SqliteVectorStore,
+    ) -> Result<Vec<i64>, StorageError> {
+        let wanted: Vec<i64> = self.quarantined()?.iter().map(|row| row.id).collect();
+        let rows = source.get(&wanted)?;
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+        let vectors: Vec<f32> = rows
+            .iter()
+            .flat_map(|row| row.vector.iter().copied())
+            .collect();
+        let payloads: Vec<Value> = rows
+            .iter()
+            .map(|row| row.payload.clone().unwrap_or(Value::Null))
+            .collect();
+        let texts: Vec<Option<&str>> = rows.iter().map(|row| row.text.as_deref()).collect();
+
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        self.write_rows(&tx, &ids, &vectors, &payloads, &texts)?;
+        append_log(&tx, &Op::Add { ids: ids.clone() })?;
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    /// Decode a row's vector after checking its length and, if it has one,
+    /// its checksum.
+    fn verify(
+        &self,
+        blob: &[u8],
+        payload: Option<&str>,
+        text: Option<&str>,
+        crc: Option<u32>,
+    ) -> Result<Vec<f32>, String> {
+        let Some(vector) = decode_vector(blob, self.dim) else {
+            return Err(format!(
+                "vector has {} bytes, expected {}",
+                blob.len(),
+                self.dim * std::mem::size_of::<f32>()
+            ));
+        };
+        match crc {
+            Some(crc) if crc != row_checksum(blob, payload, text) => {
+                Err("checksum mismatch".to_string())
+            }
+            _ => Ok(vector),
+        }
+    }
+}
+
+/// Verified ids, their flattened vectors, and corrupt ids with the reason.
+type Scan = (Vec<i64>, Vec<f32>, Vec<(i64, String)>);
+
+/// Checksum stored with each row, over its vector blob, payload and text.
+fn row_checksum(blob: &[u8], payload: Option<&str>, text: Option<&str>) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&(blob.len() as u64).to_le_bytes());
+    hasher.update(blob);
+    for field in [payload, text] {
+        match field {
+            Some(value) => {
+                hasher.update(&[1]);
+                hasher.update(&(value.len() as u64).to_le_bytes());
+                hasher.update(value.as_bytes());
+            }
+            None => hasher.update(&[0]),
+        }
+    }
+    hasher.finalize()
+}
+
+fn read_seq(conn: &Connection) -> Result<u64, StorageError> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'write_seq';",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value.and_then(|v| v.parse().ok()).unwrap_or(0))
+}
+
+/// The `dim` floats stored in `blob`, or `None` if it has another length.
+///
+/// Blobs read from SQLite carry no alignment guarantee, so the floats are
+/// copied out rather than cast in place.
+fn decode_vector(blob: &[u8], dim: usize) -> Option<Vec<f32>> {
+    if blob.len() != dim * std::mem::size_of::<f32>() {
+        return None;
+    }
+    Some(
+        blob.chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    )
+}
+
+/// Record `op` under the next write sequence number; called inside the
+/// transaction that performs it.
+fn append_log(conn: &Connection, op: &Op) -> Result<u64, StorageError> {
+    let seq = read_seq(conn)? + 1;
+    conn.execute(
+        "INSERT INTO oplog (seq, entry) VALUES (?1, ?2);",
+        params![seq as i64, serde_json::to_string(op)?],
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('write_seq', ?1);",
+        params![seq.to_string()],
+    )?;
+    Ok(seq)
 }
 
 #[cfg(test)]
@@ -103,8 +864,7 @@ mod tests {
         let tmp_dir = tempdir().expect("tempdir");
         let path = tmp_dir.path().join("vectors.sqlite");
 
-        let store = SqliteVectorStore::new(&StorageConfig { path, dim })
-            .expect("store created");
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
 
         let ids = vec![1_i64, 2_i64];
         let vectors: Vec<f32> = vec![
@@ -118,6 +878,288 @@ mod tests {
         assert_eq!(loaded_ids.len(), 2);
         assert_eq!(loaded_vecs.len(), vectors.len());
     }
-}
 
+    #[test]
+    fn delete_removes_rows_and_reports_present_ids() {
+        let dim = 4;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+
+        let ids = vec![1_i64, 2_i64];
+        let vectors: Vec<f32> = vec![
+            1.0, 0.0, 0.0, 0.0, // id 1
+            0.0, 1.0, 0.0, 0.0, // id 2
+        ];
+        store.add(&ids, &vectors).expect("add should succeed");
+
+        let deleted = store.delete(&[2, 3]).expect("delete should succeed");
+        assert_eq!(deleted, vec![2]);
+
+        let (loaded_ids, _) = store.load_all().expect("load_all");
+        assert_eq!(loaded_ids, vec![1]);
+    }
+
+    #[test]
+    fn scrub_quarantines_rows_failing_their_checksum() {
+        let dim = 2;
+        let tmp_dir = tempdir().expect("tempdir");
+        let cfg = StorageConfig {
+            path: tmp_dir.path().join("vectors.sqlite"),
+            dim,
+        };
+
+        let store = SqliteVectorStore::new(&cfg).expect("store created");
+        store
+            .add(&[1, 2, 3], &[1.0, 0.0, 0.0, 1.0, 1.0, 1.0])
+            .expect("add should succeed");
+        {
+            let conn = store.conn.lock().unwrap();
+            // A row from before checksums, and one corrupted on disk.
+            conn.execute("UPDATE vectors SET crc32 = NULL WHERE id = 1;", [])
+                .unwrap();
+            conn.execute(
+                "UPDATE vectors SET vector = ?1 WHERE id = 2;",
+                params![vec![0_u8; 8]],
+            )
+            .unwrap();
+        }
+        drop(store);
+
+        let store = SqliteVectorStore::new(&cfg).expect("store reopened");
+        assert_eq!(
+            store.get(&[1, 2]).unwrap().len(),
+            1,
+            "corrupt row is skipped"
+        );
+        assert_eq!(store.scrub().unwrap(), (3, vec![2]));
+        assert_eq!(store.quarantine_count().unwrap(), 1);
+        assert_eq!(store.quarantined().unwrap()[0].reason, "checksum mismatch");
+        assert_eq!(store.ids().unwrap(), vec![1, 3]);
+
+        // A new write supersedes the quarantined row.
+        store.add(&[2], &[0.5, 0.5]).expect("add should succeed");
+        assert_eq!(store.quarantine_count().unwrap(), 0);
+        assert_eq!(store.scrub().unwrap(), (3, vec![]));
+    }
+
+    #[test]
+    fn get_returns_only_stored_ids() {
+        let dim = 4;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+
+        store
+            .add(&[7], &[0.5, 0.25, 0.0, 1.0])
+            .expect("add should succeed");
+
+        let found = store.get(&[7, 8]).expect("get should succeed");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 7);
+        assert_eq!(found[0].vector, vec![0.5, 0.25, 0.0, 1.0]);
+        assert_eq!(found[0].payload, None);
+    }
+
+    #[test]
+    fn payloads_roundtrip_and_are_replaced_on_update() {
+        let dim = 2;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+
+        let doc = serde_json::json!({ "doc": "a.md", "tenant": 3 });
+        store
+            .add_with_payloads(&[1, 2], &[1.0, 0.0, 0.0, 1.0], &[doc.clone(), Value::Null])
+            .expect("add should succeed");
+
+        assert_eq!(store.payload(1).unwrap(), Some(doc));
+        assert_eq!(store.payload(2).unwrap(), None);
+
+        store.add(&[1], &[0.5, 0.5]).expect("update should succeed");
+        assert_eq!(store.payload(1).unwrap(), None);
+    }
+
+    #[test]
+    fn filter_ids_evaluates_predicates_against_payloads() {
+        let dim = 1;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+
+        let payloads = vec![
+            serde_json::json!({ "tenant": "a", "ts": 5, "meta": { "public": true } }),
+            serde_json::json!({ "tenant": "b", "ts": 15 }),
+            serde_json::json!({ "tenant": "a", "ts": "late" }),
+            Value::Null,
+        ];
+        store
+            .add_with_payloads(&[1, 2, 3, 4], &[0.0, 0.0, 0.0, 0.0], &payloads)
+            .expect("add should succeed");
+
+        let ids = |json: Value| -> Vec<i64> {
+            let filter: Filter = serde_json::from_value(json).unwrap();
+            let mut ids = store.filter_ids(&filter).unwrap();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(
+            ids(serde_json::json!({ "eq": { "field": "tenant", "value": "a" } })),
+            vec![1, 3]
+        );
+        assert_eq!(
+            ids(serde_json::json!({ "eq": { "field": "meta.public", "value": true } })),
+            vec![1]
+        );
+        assert_eq!(
+            ids(serde_json::json!({ "in": { "field": "ts", "values": [5, 15] } })),
+            vec![1, 2]
+        );
+        assert_eq!(
+            ids(serde_json::json!({ "range": { "field": "ts", "gte": 10 } })),
+            vec![2]
+        );
+        assert_eq!(
+            ids(serde_json::json!({ "not": { "eq": { "field": "tenant", "value": "a" } } })),
+            vec![2, 4]
+        );
+        assert_eq!(
+            ids(serde_json::json!({ "and": [
+                { "eq": { "field": "tenant", "value": "a" } },
+                { "or": [
+                    { "range": { "field": "ts", "lt": 10 } },
+                    { "eq": { "field": "ts", "value": "late" } }
+                ] }
+            ] })),
+            vec![1, 3]
+        );
+    }
+
+    #[test]
+    fn meta_values_persist() {
+        let dim = 2;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig {
+            path: path.clone(),
+            dim,
+        })
+        .expect("store created");
+        assert_eq!(store.meta("metric").unwrap(), None);
+        store.set_meta("metric", "cosine").unwrap();
+        drop(store);
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store reopened");
+        assert_eq!(store.meta("metric").unwrap().as_deref(), Some("cosine"));
+    }
 
+    #[test]
+    fn a_new_database_holds_only_the_current_schema() {
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim: 2 }).expect("store created");
+        let conn = store.conn.lock().unwrap();
+
+        let objects: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT type, name FROM sqlite_master
+                 WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name;",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected = ["meta", "oplog", "quarantine", "vectors"]
+            .map(|name| ("table".to_string(), name.to_string()));
+        assert_eq!(objects, expected);
+
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('vectors') ORDER BY cid;")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(columns, ["id", "vector", "payload", "text", "crc32"]);
+    }
+
+    #[test]
+    fn opening_a_pre_payload_database_adds_the_column() {
+        let dim = 2;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute(
+                "CREATE TABLE vectors (id INTEGER PRIMARY KEY, vector BLOB NOT NULL);",
+                [],
+            )
+            .unwrap();
+        }
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+        store
+            .add_with_payloads(&[1], &[1.0, 0.0], &[serde_json::json!("x")])
+            .expect("add should succeed");
+        assert_eq!(store.payload(1).unwrap(), Some(serde_json::json!("x")));
+    }
+
+    #[test]
+    fn writes_are_logged_with_consecutive_sequence_numbers() {
+        let dim = 2;
+        let tmp_dir = tempdir().expect("tempdir");
+        let path = tmp_dir.path().join("vectors.sqlite");
+
+        let store = SqliteVectorStore::new(&StorageConfig { path, dim }).expect("store created");
+        assert_eq!(store.write_seq().unwrap(), 0);
+
+        store
+            .add(&[1, 2, 3], &[1.0, 0.0, 0.0, 1.0, 1.0, 1.0])
+            .expect("add should succeed");
+        let seq = store.write_seq().unwrap();
+        assert_eq!(seq, 1);
+
+        store.delete(&[2, 9]).expect("delete should succeed");
+        store.add(&[3], &[0.5, 0.5]).expect("update should succeed");
+        store.set_meta("metric", "l2").expect("set_meta");
+        assert_eq!(store.write_seq().unwrap(), 4);
+
+        let ops: Vec<(u64, Op)> = store
+            .log_since(seq)
+            .expect("log_since")
+            .into_iter()
+            .map(|e| (e.seq, e.op))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (2, Op::Delete { ids: vec![2] }),
+                (3, Op::Add { ids: vec![3] }),
+                (
+                    4,
+                    Op::SetMeta {
+                        key: "metric".to_string(),
+                        value: "l2".to_string()
+                    }
+                ),
+            ]
+        );
+
+        // Deleting nothing or setting the same value again is not a write.
+        store.delete(&[42]).expect("delete should succeed");
+        store.set_meta("metric", "l2").expect("set_meta");
+        assert_eq!(store.write_seq().unwrap(), 4);
+
+        store.truncate_log(3).expect("truncate");
+        let seqs: Vec<u64> = store.log_since(0).unwrap().iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![4]);
+    }
+}
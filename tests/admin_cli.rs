@@ -0,0 +1,190 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::test_config;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+fn config(dir: &Path, max_elements: usize) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: max_elements,
+        mutable_segment_capacity: max_elements,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64]) -> Vec<f32> {
+    ids.iter()
+        .flat_map(|&id| [id as f32, (id % 3) as f32])
+        .collect()
+}
+
+/// Run the admin tool on the default store of `dir`.
+fn admin(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_self_healing_vector_db_admin"))
+        .env_clear()
+        .arg("--data-dir")
+        .arg(dir)
+        .args(["--dim", "2"])
+        .args(args)
+        .output()
+        .expect("admin tool runs")
+}
+
+/// Run the admin tool and parse the report it printed.
+fn report(dir: &Path, args: &[&str], success: bool) -> Value {
+    let output = admin(dir, args);
+    assert_eq!(
+        output.status.success(),
+        success,
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("json report")
+}
+
+#[test]
+fn inspect_verify_and_search_an_offline_store() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let data = tmp_dir.path();
+
+    // Nothing is created for a missing store.
+    let output = admin(data, &["inspect"]);
+    assert!(!output.status.success());
+    assert!(!data.join("vectors.sqlite").exists());
+
+    let mut engine = SelfHealingVectorDb::new(config(data, 64), None).expect("engine created");
+    let ids: Vec<i64> = (0..30).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    engine.delete_vectors(&[4]).expect("delete");
+    drop(engine);
+
+    let inspected = report(data, &["inspect"], true);
+    assert_eq!(inspected["rows"], 29);
+    assert_eq!(inspected["dims"]["2"], 29);
+    assert_eq!(inspected["metric"], "l2");
+    assert!(inspected["schema_version"].as_u64().unwrap() >= 1);
+    let database = data.join("vectors.sqlite");
+    assert!(
+        inspected["files"][database.to_str().unwrap()]
+            .as_u64()
+            .unwrap()
+            > 0
+    );
+
+    let verified = report(data, &["verify"], true);
+    assert_eq!(verified["checked"], 29);
+    assert_eq!(verified["consistency"]["compared"], 29);
+
+    let results = report(data, &["search", "--vector", "-7,1", "-k", "2"], true);
+    assert_eq!(results[0]["id"], 0);
+    let results = report(data, &["search", "--vector", "13.2,1", "-k", "1"], true);
+    assert_eq!(results[0]["id"], 13);
+
+    // A row changed behind the store's back fails its checksum.
+    let conn = rusqlite::Connection::open(data.join("vectors.sqlite")).expect("open");
+    conn.execute("UPDATE vectors SET payload = '{}' WHERE id = 9;", [])
+        .expect("corrupt");
+    let verified = report(data, &["verify"], false);
+    assert_eq!(verified["ok"], false);
+    assert_eq!(verified["corrupt"], json!([9]));
+}
+
+#[test]
+fn rebuild_compact_import_and_export() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let data = tmp_dir.path().join("data");
+    std::fs::create_dir(&data).expect("data dir");
+    let config_file = tmp_dir.path().join("small.toml");
+    std::fs::write(&config_file, "[engine]\ndim = 2\nhnsw_max_elements = 4\n").expect("config");
+    let config_arg = config_file.to_str().unwrap();
+
+    // Sealed segments left mostly empty by deletes.
+    let mut engine = SelfHealingVectorDb::new(config(&data, 4), None).expect("engine created");
+    let ids: Vec<i64> = (0..16).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    let deleted: Vec<i64> = ids.iter().copied().filter(|id| id % 4 != 0).collect();
+    engine.delete_vectors(&deleted).expect("delete");
+    engine.snapshot().expect("snapshot");
+    drop(engine);
+
+    let compacted = report(&data, &["--config", config_arg, "compact"], true);
+    assert_eq!(
+        (&compacted["size"], &compacted["segments"]),
+        (&json!(4), &json!(2))
+    );
+    // Saved, although no write happened since the last snapshot.
+    assert_eq!(compacted["snapshot"]["segments"], 2);
+
+    let rebuilt = report(&data, &["--config", config_arg, "rebuild"], true);
+    assert_eq!(rebuilt["size"], 4);
+    assert!(rebuilt["snapshot"].is_object());
+
+    let exported = tmp_dir.path().join("vectors.jsonl");
+    let written = report(&data, &["export", exported.to_str().unwrap()], true);
+    assert_eq!(written["exported"], 4);
+    assert_eq!(
+        std::fs::read_to_string(&exported).unwrap().lines().count(),
+        4
+    );
+
+    let imported = report(
+        &data,
+        &[
+            "--config",
+            config_arg,
+            "import",
+            exported.to_str().unwrap(),
+            "--batch-size",
+            "3",
+        ],
+        true,
+    );
+    assert_eq!(imported["imported"], 4);
+    assert_eq!(imported["batches"], 2);
+    assert_eq!(imported["index"]["size"], 4);
+    assert_eq!(
+        report(&data, &["--config", config_arg, "verify"], true)["ok"],
+        true
+    );
+}
+
+#[test]
+fn backup_and_restore_an_offline_store() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let data = tmp_dir.path().join("data");
+    std::fs::create_dir(&data).expect("data dir");
+    let bundle = tmp_dir.path().join("bundle");
+
+    let mut engine = SelfHealingVectorDb::new(config(&data, 64), None).expect("engine created");
+    let ids: Vec<i64> = (0..10).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    drop(engine);
+
+    let manifest = report(&data, &["backup", bundle.to_str().unwrap()], true);
+    assert_eq!(manifest["points"], 10);
+    assert_eq!(manifest["dim"], 2);
+
+    // Written after the backup, so lost by the restore.
+    let mut engine = SelfHealingVectorDb::new(config(&data, 64), None).expect("engine reopened");
+    engine
+        .add_vectors(&[10, 11], &vectors(&[10, 11]))
+        .expect("add");
+    drop(engine);
+
+    let restored = report(&data, &["restore", bundle.to_str().unwrap()], true);
+    assert_eq!(restored["points"], 10);
+    assert_eq!(report(&data, &["inspect"], true)["rows"], 10);
+    assert_eq!(report(&data, &["verify"], true)["ok"], true);
+
+    // A missing bundle changes nothing.
+    let missing = tmp_dir.path().join("missing");
+    assert!(!admin(&data, &["restore", missing.to_str().unwrap()])
+        .status
+        .success());
+    assert_eq!(report(&data, &["inspect"], true)["rows"], 10);
+}
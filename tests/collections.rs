@@ -0,0 +1,126 @@
+use self_healing_vector_db::collections::{CollectionCatalog, CollectionConfig, CollectionError};
+use self_healing_vector_db::engine::EngineError;
+use self_healing_vector_db::Metric;
+use tempfile::tempdir;
+
+fn config(dim: usize) -> CollectionConfig {
+    CollectionConfig {
+        dim,
+        metric: Metric::L2,
+        hnsw_max_elements: 1_000,
+        mutable_segment_capacity: 1_000,
+        hnsw_m: 16,
+        hnsw_ef_construction: 200,
+        hnsw_ef_search: 64,
+    }
+}
+
+#[test]
+fn collections_are_independent_and_survive_reopen() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let mut catalog = CollectionCatalog::open(tmp_dir.path()).expect("catalog opened");
+    let mut small = catalog
+        .create("small", config(2), None)
+        .expect("create small");
+    let mut large = catalog
+        .create("large", config(4), None)
+        .expect("create large");
+
+    small.add_vectors(&[1], &[1.0, 0.0]).expect("add to small");
+    large
+        .add_vectors(&[1, 2], &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+        .expect("add to large");
+    assert!(small.add_vectors(&[2], &[1.0, 0.0, 0.0, 0.0]).is_err());
+
+    drop((small, large, catalog));
+
+    let catalog = CollectionCatalog::open(tmp_dir.path()).expect("catalog reopened");
+    let names: Vec<&str> = catalog.list().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["large", "small"]);
+    assert_eq!(catalog.get("large"), Some(&config(4)));
+
+    let small = catalog.open_collection("small", None).expect("open small");
+    let large = catalog.open_collection("large", None).expect("open large");
+    assert_eq!(small.health().size, 1);
+    assert_eq!(large.health().size, 2);
+}
+
+#[test]
+fn drop_removes_collection_and_its_data() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let mut catalog = CollectionCatalog::open(tmp_dir.path()).expect("catalog opened");
+    let mut docs = catalog
+        .create("docs", config(2), None)
+        .expect("create docs");
+    docs.add_vectors(&[1], &[1.0, 0.0]).expect("add");
+    drop(docs);
+
+    assert!(matches!(
+        catalog.create("docs", config(2), None),
+        Err(CollectionError::AlreadyExists(_))
+    ));
+
+    catalog.drop_collection("docs").expect("drop docs");
+    assert!(!catalog.storage_path("docs").exists());
+    assert!(matches!(
+        catalog.open_collection("docs", None),
+        Err(CollectionError::NotFound(_))
+    ));
+
+    // Re-creating a dropped collection starts empty.
+    let docs = catalog
+        .create("docs", config(2), None)
+        .expect("re-create docs");
+    assert_eq!(docs.health().size, 0);
+}
+
+#[test]
+fn collection_names_are_validated() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut catalog = CollectionCatalog::open(tmp_dir.path()).expect("catalog opened");
+
+    for name in ["", "../escape", "has space", "dot.name"] {
+        assert!(
+            matches!(
+                catalog.create(name, config(2), None),
+                Err(CollectionError::InvalidName(_))
+            ),
+            "{name:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn collection_configs_are_validated() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut catalog = CollectionCatalog::open(tmp_dir.path()).expect("catalog opened");
+
+    let invalid = [
+        config(0),
+        CollectionConfig {
+            hnsw_m: 257,
+            ..config(2)
+        },
+        CollectionConfig {
+            hnsw_ef_search: 0,
+            ..config(2)
+        },
+        CollectionConfig {
+            hnsw_max_elements: 0,
+            ..config(2)
+        },
+    ];
+    for cfg in invalid {
+        assert!(
+            matches!(
+                catalog.create("bad", cfg.clone(), None),
+                Err(CollectionError::Engine(EngineError::InvalidParameter(_)))
+            ),
+            "{cfg:?} should be rejected"
+        );
+    }
+    assert_eq!(catalog.list().count(), 0);
+    assert!(!catalog.storage_path("bad").exists());
+}
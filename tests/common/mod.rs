@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use self_healing_vector_db::{EngineConfig, Metric};
+
+/// A small L2 engine storing `vectors.sqlite` in `dir`; tests override the
+/// fields they exercise with `..test_config(dir)`.
+pub fn test_config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 4,
+        metric: Metric::L2,
+        storage_path: dir.join("vectors.sqlite"),
+        hnsw_max_elements: 10_000,
+        mutable_segment_capacity: 10_000,
+        hnsw_m: 16,
+        hnsw_ef_construction: 200,
+        hnsw_ef_search: 64,
+    }
+}
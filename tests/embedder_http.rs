@@ -0,0 +1,175 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Json, Router};
+use self_healing_vector_db::embeddings::{
+    Embedder, EmbeddingError, HttpEmbedder, HttpEmbedderConfig,
+};
+use serde_json::{json, Value};
+
+/// Local stand-in for an OpenAI-compatible embedding server.
+#[derive(Default)]
+struct Stub {
+    calls: AtomicUsize,
+    /// Answer the first `fail_first` calls with `fail_status`.
+    fail_first: usize,
+    fail_status: u16,
+    delay: Duration,
+    requests: Mutex<Vec<(Option<String>, Value)>>,
+}
+
+/// Embeds each text as `[len, 1]` and lists the results in reverse order, so
+/// the client has to reorder them by `index`.
+async fn embeddings_handler(
+    State(stub): State<Arc<Stub>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let call = stub.calls.fetch_add(1, Ordering::SeqCst);
+    let auth = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    stub.requests.lock().unwrap().push((auth, body.clone()));
+
+    if call < stub.fail_first {
+        return Err(StatusCode::from_u16(stub.fail_status).unwrap());
+    }
+    let delay = stub.delay;
+    tokio::task::spawn_blocking(move || std::thread::sleep(delay))
+        .await
+        .unwrap();
+
+    let inputs = body["input"].as_array().cloned().unwrap_or_default();
+    let data: Vec<Value> = inputs
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, text)| {
+            let len = text.as_str().unwrap_or_default().len();
+            json!({"object": "embedding", "index": i, "embedding": [len as f32, 1.0]})
+        })
+        .collect();
+    Ok(Json(
+        json!({"object": "list", "data": data, "model": body["model"]}),
+    ))
+}
+
+fn serve(stub: Arc<Stub>) -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    listener.set_nonblocking(true).expect("nonblocking");
+
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().expect("runtime");
+        rt.block_on(async move {
+            let app = Router::new()
+                .route("/v1/embeddings", post(embeddings_handler))
+                .with_state(stub);
+            let listener = tokio::net::TcpListener::from_std(listener).expect("listener");
+            axum::serve(listener, app).await.expect("serve");
+        });
+    });
+    addr
+}
+
+fn config(addr: SocketAddr) -> HttpEmbedderConfig {
+    HttpEmbedderConfig {
+        initial_backoff: Duration::from_millis(10),
+        ..HttpEmbedderConfig::new(format!("http://{addr}"), "tiny-model")
+    }
+}
+
+fn texts(items: &[&str]) -> Vec<String> {
+    items.iter().map(|t| t.to_string()).collect()
+}
+
+#[test]
+fn batches_requests_and_keeps_input_order() {
+    let stub = Arc::new(Stub::default());
+    let embedder = HttpEmbedder::new(HttpEmbedderConfig {
+        api_key: Some("secret".to_string()),
+        batch_size: 2,
+        ..config(serve(stub.clone()))
+    })
+    .expect("embedder");
+
+    let vectors = embedder
+        .embed(&texts(&["a", "bb", "ccc", "dddd", "eeeee"]))
+        .expect("embed should succeed");
+    let lens: Vec<f32> = vectors.iter().map(|v| v[0]).collect();
+    assert_eq!(lens, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    let requests = stub.requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    for (auth, body) in requests.iter() {
+        assert_eq!(auth.as_deref(), Some("Bearer secret"));
+        assert_eq!(body["model"], "tiny-model");
+    }
+    assert_eq!(requests[2].1["input"], json!(["eeeee"]));
+}
+
+#[test]
+fn retries_transient_failures_with_backoff() {
+    let stub = Arc::new(Stub {
+        fail_first: 2,
+        fail_status: 503,
+        ..Stub::default()
+    });
+    let embedder = HttpEmbedder::new(config(serve(stub.clone()))).expect("embedder");
+
+    let vectors = embedder
+        .embed(&texts(&["hello"]))
+        .expect("third attempt succeeds");
+    assert_eq!(vectors, vec![vec![5.0, 1.0]]);
+    assert_eq!(stub.calls.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let stub = Arc::new(Stub {
+        fail_first: usize::MAX,
+        fail_status: 401,
+        ..Stub::default()
+    });
+    let embedder = HttpEmbedder::new(config(serve(stub.clone()))).expect("embedder");
+
+    let err = embedder.embed(&texts(&["hello"])).unwrap_err();
+    assert!(matches!(err, EmbeddingError::Status { status: 401, .. }));
+    assert_eq!(stub.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn slow_responses_time_out() {
+    let stub = Arc::new(Stub {
+        delay: Duration::from_millis(500),
+        ..Stub::default()
+    });
+    let embedder = HttpEmbedder::new(HttpEmbedderConfig {
+        timeout: Duration::from_millis(100),
+        max_retries: 1,
+        ..config(serve(stub.clone()))
+    })
+    .expect("embedder");
+
+    let err = embedder.embed(&texts(&["hello"])).unwrap_err();
+    assert!(matches!(err, EmbeddingError::Timeout), "got {err:?}");
+    assert_eq!(stub.calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn unreachable_server_is_a_transport_error() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("addr");
+    let embedder = HttpEmbedder::new(HttpEmbedderConfig {
+        max_retries: 0,
+        ..config(addr)
+    })
+    .expect("embedder");
+
+    let err = embedder.embed(&texts(&["hello"])).unwrap_err();
+    assert!(matches!(err, EmbeddingError::Transport(_)), "got {err:?}");
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "onnx")]
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::test_config;
+use self_healing_vector_db::embeddings::{Embedder, OnnxEmbedder, OnnxEmbedderConfig, Pooling};
+use self_healing_vector_db::engine::EngineError;
+use self_healing_vector_db::{EngineConfig, Metric, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+/// Gather-only model with a 4-dimensional embedding table; see `generate.py`.
+fn fixture() -> OnnxEmbedderConfig {
+    OnnxEmbedderConfig::new(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tiny-embedder"),
+    )
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[test]
+fn mean_pooled_embeddings_are_normalized_and_batched() {
+    let embedder = OnnxEmbedder::new(OnnxEmbedderConfig {
+        batch_size: 2,
+        ..fixture()
+    })
+    .expect("fixture model loads");
+    assert_eq!(embedder.dim(), Some(4));
+
+    let texts: Vec<String> = ["cat", "dog", "truck", "car", "cat dog fish"]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+    let vectors = embedder.embed(&texts).expect("embed should succeed");
+    assert_eq!(vectors.len(), texts.len());
+    for v in &vectors {
+        assert!((cosine(v, v) - 1.0).abs() < 1e-5);
+    }
+    assert!(cosine(&vectors[0], &vectors[1]) > cosine(&vectors[0], &vectors[2]));
+
+    // Padding in a mixed-length batch must not change a text's embedding.
+    let alone = embedder.embed(&texts[..1]).expect("embed should succeed");
+    for (a, b) in alone[0].iter().zip(&vectors[0]) {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn cls_pooling_returns_the_first_token_vector() {
+    let embedder = OnnxEmbedder::new(OnnxEmbedderConfig {
+        pooling: Pooling::Cls,
+        normalize: false,
+        ..fixture()
+    })
+    .expect("fixture model loads");
+
+    let vectors = embedder
+        .embed(&["cat".to_string(), "truck car".to_string()])
+        .expect("embed should succeed");
+    assert_eq!(vectors, vec![vec![0.0, 0.0, 1.0, 0.0]; 2]);
+}
+
+#[test]
+fn engine_rejects_a_model_with_a_different_dim() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let embedder = OnnxEmbedder::new(fixture()).expect("fixture model loads");
+
+    let cfg = EngineConfig {
+        dim: 8,
+        metric: Metric::Cosine,
+        ..test_config(tmp_dir.path())
+    };
+    let err = SelfHealingVectorDb::new(cfg, Some(Arc::new(embedder)))
+        .err()
+        .expect("open should fail");
+    assert!(matches!(err, EngineError::Embedding(_)));
+}
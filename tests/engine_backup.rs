@@ -0,0 +1,123 @@
+mod common;
+
+use std::path::Path;
+
+use common::test_config;
+use self_healing_vector_db::backup::{database_path, BackupError};
+use self_healing_vector_db::engine::EngineError;
+use self_healing_vector_db::snapshot::snapshot_dir;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 8,
+        mutable_segment_capacity: 8,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64]) -> Vec<f32> {
+    ids.iter()
+        .flat_map(|&id| [id as f32, (id % 4) as f32])
+        .collect()
+}
+
+fn assert_invalid(result: Result<impl std::fmt::Debug, EngineError>) {
+    match result {
+        Err(EngineError::Backup(BackupError::Invalid(_))) => {}
+        other => panic!("expected an invalid backup, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_backup_restores_the_data_as_of_when_it_was_taken() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let data = tmp_dir.path().join("data");
+    let bundle = tmp_dir.path().join("backup");
+    std::fs::create_dir(&data).expect("data dir");
+
+    let mut engine = SelfHealingVectorDb::new(config(&data), None).expect("engine created");
+    let ids: Vec<i64> = (0..20).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    engine.delete_vectors(&[5]).expect("delete");
+
+    let manifest = engine.backup(&bundle).expect("backup");
+    assert_eq!(manifest.points, 19);
+    assert!(database_path(&bundle).is_file());
+
+    // Later writes, and a snapshot of them, are undone by the restore.
+    let later: Vec<i64> = (20..30).collect();
+    engine.add_vectors(&later, &vectors(&later)).expect("add");
+    engine.delete_vectors(&[0, 1]).expect("delete");
+    engine.snapshot().expect("snapshot");
+
+    let restored = engine.restore_backup(&bundle).expect("restore");
+    assert_eq!(restored, manifest);
+    assert_eq!(engine.health().size, 19);
+    assert_eq!(engine.get(&[0, 1]).expect("get").len(), 2);
+    assert!(engine.get(&[5, 20]).expect("get").is_empty());
+    let results = engine.search(&[7.0, 3.0], 1).expect("search");
+    assert_eq!(results[0].id, 7);
+    assert!(engine.repair().expect("repair").is_consistent());
+
+    // Writes continue from the restored state and survive a restart.
+    engine.add_vectors(&[40], &vectors(&[40])).expect("add");
+    drop(engine);
+    let mut engine = SelfHealingVectorDb::new(config(&data), None).expect("engine reopened");
+    assert_eq!(engine.health().size, 20);
+    assert!(engine.repair().expect("repair").is_consistent());
+}
+
+#[test]
+fn damaged_or_mismatched_bundles_are_rejected_without_changing_anything() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let data = tmp_dir.path().join("data");
+    let bundle = tmp_dir.path().join("backup");
+    std::fs::create_dir(&data).expect("data dir");
+
+    let mut engine = SelfHealingVectorDb::new(config(&data), None).expect("engine created");
+    let ids: Vec<i64> = (0..10).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    engine.backup(&bundle).expect("backup");
+    engine.add_vectors(&[10], &vectors(&[10])).expect("add");
+
+    // An existing bundle is never overwritten.
+    match engine.backup(&bundle) {
+        Err(EngineError::Backup(BackupError::DestinationNotEmpty(_))) => {}
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+
+    // A directory that is not a bundle.
+    assert_invalid(engine.restore_backup(&data));
+
+    // A different dimension.
+    let other_dir = tmp_dir.path().join("other");
+    std::fs::create_dir(&other_dir).expect("other dir");
+    let mut other = SelfHealingVectorDb::new(
+        EngineConfig {
+            dim: 3,
+            ..config(&other_dir)
+        },
+        None,
+    )
+    .expect("engine created");
+    assert_invalid(other.restore_backup(&bundle));
+
+    // A row corrupted after the backup was taken.
+    let conn = rusqlite::Connection::open(database_path(&bundle)).expect("open");
+    conn.execute("UPDATE vectors SET vector = zeroblob(8) WHERE id = 3;", [])
+        .expect("corrupt");
+    assert_invalid(engine.restore_backup(&bundle));
+    conn.execute("DELETE FROM vectors WHERE id = 3;", [])
+        .expect("delete");
+    assert_invalid(engine.restore_backup(&bundle));
+
+    // A missing snapshot.
+    std::fs::remove_dir_all(snapshot_dir(&database_path(&bundle))).expect("remove");
+    assert_invalid(engine.restore_backup(&bundle));
+
+    assert_eq!(engine.health().size, 11);
+    assert!(engine.repair().expect("repair").is_consistent());
+}
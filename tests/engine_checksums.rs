@@ -0,0 +1,106 @@
+mod common;
+
+use std::path::Path;
+
+use common::test_config;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 1_000,
+        mutable_segment_capacity: 1_000,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64]) -> Vec<f32> {
+    ids.iter()
+        .flat_map(|&id| [id as f32, (id % 3) as f32])
+        .collect()
+}
+
+/// Overwrite the stored vectors of `ids` behind the engine's back, keeping
+/// their length so only the checksum can tell.
+fn corrupt(path: &Path, ids: &[i64]) {
+    let conn = rusqlite::Connection::open(path).expect("open");
+    for &id in ids {
+        conn.execute(
+            "UPDATE vectors SET vector = zeroblob(8) WHERE id = ?1;",
+            [id],
+        )
+        .expect("corrupt");
+    }
+}
+
+#[test]
+fn scrub_quarantines_corrupt_rows_and_health_counts_them() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = config(tmp_dir.path());
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let ids: Vec<i64> = (1..=10).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+
+    corrupt(&cfg.storage_path, &[4, 7]);
+    assert!(engine.get(&[4, 7]).expect("get").is_empty());
+
+    let report = engine.scrub().expect("scrub");
+    assert_eq!(report.checked, 10);
+    assert_eq!(report.quarantined, vec![4, 7]);
+
+    let health = engine.health();
+    assert!(!health.ok);
+    assert_eq!(health.reason, "rows-quarantined");
+    assert_eq!((health.size, health.quarantined), (8, 2));
+    let results = engine.search(&[4.0, 1.0], 10).expect("search");
+    assert!(results.iter().all(|r| r.id != 4 && r.id != 7));
+
+    // Rewriting or deleting a row releases it from quarantine.
+    engine.add_vectors(&[4], &vectors(&[4])).expect("add");
+    engine.delete_vectors(&[7]).expect("delete");
+    let health = engine.health();
+    assert!(health.ok, "{}", health.reason);
+    assert_eq!((health.size, health.quarantined), (9, 0));
+    assert_eq!(
+        engine.scrub().expect("scrub").quarantined,
+        Vec::<i64>::new()
+    );
+}
+
+#[test]
+fn quarantined_rows_are_restored_from_a_backup() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = config(tmp_dir.path());
+    let backup = tmp_dir.path().join("backup.sqlite");
+    let ids: Vec<i64> = (1..=10).collect();
+    {
+        let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+        engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    }
+    std::fs::copy(&cfg.storage_path, &backup).expect("backup");
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine reopened");
+    engine.snapshot().expect("snapshot");
+    corrupt(&cfg.storage_path, &[2, 5, 9]);
+    // Row 9 is gone from the backup as well, so it cannot be restored.
+    corrupt(&backup, &[9]);
+    assert_eq!(engine.scrub().expect("scrub").quarantined, vec![2, 5, 9]);
+
+    let report = engine.restore_quarantined(&backup).expect("restore");
+    assert_eq!(report.restored, vec![2, 5]);
+    assert_eq!(report.remaining, 1);
+    assert_eq!(engine.health().size, 9);
+    let restored = engine.get(&[2, 5]).expect("get");
+    assert_eq!(restored[0].vector, vec![2.0, 2.0]);
+    assert_eq!(restored[1].vector, vec![5.0, 2.0]);
+    let results = engine.search(&[5.0, 2.0], 1).expect("search");
+    assert_eq!(results[0].id, 5);
+
+    // The restore is logged, so the snapshot is brought up to date on restart.
+    drop(engine);
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+    let health = engine.health();
+    assert_eq!((health.size, health.quarantined), (9, 1));
+    assert!(engine.repair().expect("repair").is_consistent());
+}
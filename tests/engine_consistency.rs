@@ -0,0 +1,79 @@
+mod common;
+
+use common::test_config;
+use rusqlite::{params, Connection};
+use self_healing_vector_db::health::RepairAction;
+use self_healing_vector_db::SelfHealingVectorDb;
+use tempfile::tempdir;
+
+fn blob(v: &[f32]) -> Vec<u8> {
+    v.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+#[test]
+fn drift_between_storage_and_index_is_detected_and_repaired() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = test_config(tmp_dir.path());
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let ids: Vec<i64> = (0..20).collect();
+    let flat: Vec<f32> = (0..20).flat_map(|i| [i as f32, 0.0, 0.0, 1.0]).collect();
+    engine.add_vectors(&ids, &flat).expect("add_vectors");
+
+    let report = engine.check_consistency().expect("check");
+    assert!(report.is_consistent(), "{report:?}");
+    assert_eq!(report.compared, 20);
+    assert!(engine.health().ok);
+
+    // Change storage behind the engine's back. Rows without a checksum are
+    // trusted, so these read as valid writes rather than corruption.
+    let conn = Connection::open(&cfg.storage_path).expect("open sqlite");
+    conn.execute(
+        "INSERT INTO vectors (id, vector) VALUES (?1, ?2);",
+        params![100, blob(&[0.0, 9.0, 9.0, 9.0])],
+    )
+    .unwrap();
+    conn.execute("DELETE FROM vectors WHERE id = 3;", [])
+        .unwrap();
+    conn.execute(
+        "UPDATE vectors SET vector = ?1, crc32 = NULL WHERE id = 7;",
+        params![blob(&[0.0, -5.0, 0.0, 0.0])],
+    )
+    .unwrap();
+
+    let report = engine.check_consistency().expect("check");
+    assert_eq!(
+        (report.missing, report.extra, report.stale),
+        (1, 1, 1),
+        "{report:?}"
+    );
+    assert_eq!(report.examples, vec![100, 3, 7]);
+    assert_eq!(report.repair, None);
+    let health = engine.health();
+    assert!(!health.ok);
+    assert_eq!(health.reason, "index-inconsistent");
+
+    let report = engine.repair().expect("repair");
+    assert_eq!(report.repair, Some(RepairAction::Reinserted));
+    assert!(engine.health().ok);
+    assert!(engine.check_consistency().expect("check").is_consistent());
+
+    let results = engine.search(&[0.0, 9.0, 9.0, 9.0], 1).expect("search");
+    assert_eq!(results[0].id, 100);
+    let results = engine.search(&[0.0, -5.0, 0.0, 0.0], 1).expect("search");
+    assert_eq!(results[0].id, 7);
+    let results = engine.search(&[3.0, 0.0, 0.0, 1.0], 20).expect("search");
+    assert!(results.iter().all(|r| r.id != 3));
+
+    // Losing most of the index is fixed by a rebuild.
+    conn.execute(
+        "UPDATE vectors SET vector = ?1, crc32 = NULL;",
+        params![blob(&[1.0, 1.0, 1.0, 1.0])],
+    )
+    .unwrap();
+    let report = engine.repair().expect("repair");
+    assert_eq!(report.stale, 20);
+    assert_eq!(report.repair, Some(RepairAction::Rebuilt));
+    assert!(engine.check_consistency().expect("check").is_consistent());
+    assert_eq!(engine.health().size, 20);
+}
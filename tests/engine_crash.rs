@@ -0,0 +1,234 @@
+//! Crash tests: a child process (this test binary re-run on `crash_child`)
+//! writes through the engine and is killed between two steps of a write,
+//! then the parent reopens the data and checks storage and index agree.
+
+mod common;
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Duration;
+
+use common::test_config;
+use self_healing_vector_db::snapshot::snapshot_dir;
+use self_healing_vector_db::storage::{SqliteVectorStore, StorageConfig};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 8,
+        mutable_segment_capacity: 8,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64]) -> Vec<f32> {
+    ids.iter()
+        .flat_map(|&id| [id as f32, (id % 7) as f32])
+        .collect()
+}
+
+fn child(dir: &Path, scenario: &str) -> Command {
+    let mut cmd = Command::new(std::env::current_exe().expect("test binary"));
+    cmd.args(["crash_child", "--exact", "--ignored", "--nocapture"])
+        .env("CRASH_DIR", dir)
+        .env("CRASH_SCENARIO", scenario)
+        .stdout(Stdio::null());
+    cmd
+}
+
+fn run_child(dir: &Path, scenario: &str) -> ExitStatus {
+    let status = child(dir, scenario).status().expect("child ran");
+    assert!(!status.success(), "{scenario}: child should have crashed");
+    status
+}
+
+/// Reopen the engine and require storage and index to agree in full.
+fn reopen_consistent(dir: &Path) -> SelfHealingVectorDb {
+    let mut engine = SelfHealingVectorDb::new(config(dir), None).expect("engine reopened");
+    let report = engine.repair().expect("repair");
+    assert!(report.is_consistent(), "{report:?}");
+    engine
+}
+
+fn has_snapshot(dir: &Path) -> bool {
+    snapshot_dir(&config(dir).storage_path)
+        .join("manifest.json")
+        .exists()
+}
+
+/// Scenarios run in the child process; each ends by aborting.
+#[test]
+#[ignore = "run by the crash tests in a child process"]
+fn crash_child() {
+    let (Ok(dir), Ok(scenario)) = (std::env::var("CRASH_DIR"), std::env::var("CRASH_SCENARIO"))
+    else {
+        return;
+    };
+    let dir = PathBuf::from(dir);
+    let mut engine = SelfHealingVectorDb::new(config(&dir), None).expect("engine created");
+
+    if scenario == "ingest" {
+        ingest_until_killed(&dir, &mut engine);
+    }
+
+    let ids: Vec<i64> = (0..20).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+
+    match scenario.as_str() {
+        // Storage committed, the index not yet updated.
+        "after_storage_commit" => {
+            let store = SqliteVectorStore::new(&StorageConfig {
+                path: config(&dir).storage_path,
+                dim: 2,
+            })
+            .expect("store opened");
+            let ids: Vec<i64> = (20..25).collect();
+            store.add(&ids, &vectors(&ids)).expect("add");
+            store.delete(&[3]).expect("delete");
+        }
+        // A write transaction that never committed.
+        "mid_transaction" => {
+            engine
+                .add_vectors(&[20, 21], &vectors(&[20, 21]))
+                .expect("add");
+            let conn = rusqlite::Connection::open(config(&dir).storage_path).expect("open");
+            conn.execute_batch(
+                "BEGIN;
+                 INSERT INTO vectors (id, vector) VALUES (99, x'0000803f0000803f');
+                 INSERT INTO oplog (seq, entry) VALUES (1000, '{\"op\":\"add\",\"ids\":[99]}');",
+            )
+            .expect("uncommitted writes");
+        }
+        // Writes after the snapshot, then a crash while saving the next one.
+        "during_snapshot" => {
+            let ids: Vec<i64> = (20..30).collect();
+            engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+            engine.delete_vectors(&[0]).expect("delete");
+            let dir = snapshot_dir(&config(&dir).storage_path);
+            std::fs::write(dir.join("seg-9-99.hnsw.graph"), b"partial").expect("write");
+            std::fs::write(dir.join("manifest.json.tmp"), b"{\"version\":").expect("write");
+        }
+        other => panic!("unknown scenario {other}"),
+    }
+    std::process::abort();
+}
+
+/// Add batches of ten ids, deleting every tenth id a batch later and taking
+/// a snapshot every few batches, and record acknowledged ids in `acked`.
+fn ingest_until_killed(dir: &Path, engine: &mut SelfHealingVectorDb) -> ! {
+    let acked_path = dir.join("acked");
+    let acked = std::fs::read_to_string(&acked_path).unwrap_or_default();
+    let mut next = acked
+        .lines()
+        .filter_map(|line| line.parse::<i64>().ok())
+        .max()
+        // A kill while acknowledging leaves a partial batch; start the next
+        // one on a boundary so only ids divisible by ten are ever deleted.
+        .map_or(0, |id| (id / 10 + 1) * 10);
+    let mut acked = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&acked_path)
+        .expect("acked file");
+
+    for batch in 0.. {
+        let ids: Vec<i64> = (next..next + 10).collect();
+        engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+        for id in &ids {
+            writeln!(acked, "{id}").expect("ack");
+        }
+        if next >= 10 {
+            engine.delete_vectors(&[next - 10]).expect("delete");
+        }
+        if batch % 3 == 2 {
+            engine.snapshot().expect("snapshot");
+        }
+        next += 10;
+    }
+    unreachable!()
+}
+
+#[test]
+fn writes_committed_before_a_crash_are_replayed_from_the_log() {
+    let tmp_dir = tempdir().expect("tempdir");
+    run_child(tmp_dir.path(), "after_storage_commit");
+
+    let engine = reopen_consistent(tmp_dir.path());
+    assert!(
+        has_snapshot(tmp_dir.path()),
+        "restored from the snapshot, not rebuilt"
+    );
+    assert_eq!(engine.health().size, 24);
+    assert!(engine.get(&[3]).expect("get").is_empty());
+    let results = engine.search(&[3.0, 3.0], 24).expect("search");
+    assert!(results.iter().all(|r| r.id != 3));
+    assert!(results.iter().any(|r| r.id == 24));
+}
+
+#[test]
+fn uncommitted_writes_vanish_after_a_crash() {
+    let tmp_dir = tempdir().expect("tempdir");
+    run_child(tmp_dir.path(), "mid_transaction");
+
+    let engine = reopen_consistent(tmp_dir.path());
+    assert!(has_snapshot(tmp_dir.path()));
+    assert_eq!(engine.health().size, 22);
+    assert!(engine.get(&[99]).expect("get").is_empty());
+}
+
+#[test]
+fn a_crash_while_saving_a_snapshot_keeps_the_previous_one() {
+    let tmp_dir = tempdir().expect("tempdir");
+    run_child(tmp_dir.path(), "during_snapshot");
+
+    let engine = reopen_consistent(tmp_dir.path());
+    assert!(has_snapshot(tmp_dir.path()));
+    assert_eq!(engine.health().size, 29);
+
+    // The next snapshot clears out the leftovers.
+    engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    let dir = snapshot_dir(&config(tmp_dir.path()).storage_path);
+    for leftover in ["seg-9-99.hnsw.graph", "manifest.json.tmp"] {
+        assert!(!dir.join(leftover).exists(), "{leftover} was removed");
+    }
+}
+
+#[test]
+fn killing_the_process_during_ingestion_loses_no_acknowledged_write() {
+    let tmp_dir = tempdir().expect("tempdir");
+    for delay in [40, 90, 160, 250] {
+        let mut process = child(tmp_dir.path(), "ingest")
+            .spawn()
+            .expect("child spawned");
+        std::thread::sleep(Duration::from_millis(delay));
+        process.kill().expect("child killed");
+        process.wait().expect("child reaped");
+
+        let engine = reopen_consistent(tmp_dir.path());
+        // Every tenth id may be deleted; all other acknowledged ids must be stored.
+        let acked: Vec<i64> = std::fs::read_to_string(tmp_dir.path().join("acked"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .filter(|id: &i64| id % 10 != 0)
+            .collect();
+        let stored: HashSet<i64> = engine
+            .get(&acked)
+            .expect("get")
+            .into_iter()
+            .map(|row| row.id)
+            .collect();
+        assert_eq!(stored.len(), acked.len(), "after a kill at {delay}ms");
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+#[test]
+fn deleted_vectors_stay_gone_after_restart() {
+    let dim = 4;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        ..test_config(tmp_dir.path())
+    };
+
+    let ids = vec![1_i64, 2_i64];
+    let flat = vec![
+        1.0, 0.0, 0.0, 0.0, // id 1
+        0.0, 1.0, 0.0, 0.0, // id 2
+    ];
+    let query = [1.0, 0.0, 0.0, 0.0];
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    engine
+        .add_vectors(&ids, &flat)
+        .expect("add_vectors should succeed");
+
+    let deleted = engine
+        .delete_vectors(&[1, 42])
+        .expect("delete_vectors should succeed");
+    assert_eq!(deleted, 1, "only id 1 was present");
+
+    let results = engine.search(&query, 2).expect("search should succeed");
+    assert!(
+        results.iter().all(|r| r.id != 1),
+        "deleted id must not be returned by search"
+    );
+    assert_eq!(engine.health().size, 1);
+
+    drop(engine);
+
+    let engine2 = SelfHealingVectorDb::new(cfg, None).expect("engine recreated");
+    assert_eq!(engine2.health().size, 1, "delete must survive a restart");
+
+    let results2 = engine2
+        .search(&query, 2)
+        .expect("search after restart should succeed");
+    assert_eq!(results2.len(), 1);
+    assert_eq!(results2[0].id, 2);
+}
@@ -1,5 +1,6 @@
-use std::path::PathBuf;
+mod common;
 
+use common::test_config;
 use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
 use tempfile::tempdir;
 
@@ -7,19 +8,12 @@ use tempfile::tempdir;
 fn health_on_fresh_engine_reports_empty() {
     let dim = 384;
     let tmp_dir = tempdir().expect("tempdir");
-    let db_path: PathBuf = tmp_dir.path().join("vectors.sqlite");
-
     let cfg = EngineConfig {
         dim,
-        storage_path: db_path,
-        hnsw_max_elements: 10_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
+        ..test_config(tmp_dir.path())
     };
 
-    let engine =
-        SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
 
     let health = engine.health();
     assert!(health.ok, "fresh engine should be healthy");
@@ -31,19 +25,12 @@ fn health_on_fresh_engine_reports_empty() {
 fn search_on_empty_engine_returns_no_results() {
     let dim = 384;
     let tmp_dir = tempdir().expect("tempdir");
-    let db_path: PathBuf = tmp_dir.path().join("vectors.sqlite");
-
     let cfg = EngineConfig {
         dim,
-        storage_path: db_path,
-        hnsw_max_elements: 10_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
+        ..test_config(tmp_dir.path())
     };
 
-    let engine =
-        SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
 
     let query = vec![0.0_f32; dim];
     let results = engine.search(&query, 10).expect("search should not error");
@@ -52,5 +39,3 @@ fn search_on_empty_engine_returns_no_results() {
         "search on an empty index should yield no results"
     );
 }
-
-
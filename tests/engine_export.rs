@@ -0,0 +1,131 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::Path;
+
+use common::test_config;
+use self_healing_vector_db::export::ExportFormat;
+use self_healing_vector_db::import::{self, ImportFormat, ImportOptions, ImportRecord};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 4_000,
+        mutable_segment_capacity: 4_000,
+        ..test_config(dir)
+    }
+}
+
+/// An engine with 1500 vectors, more than one page of rows, every tenth
+/// with a payload.
+fn engine(dir: &Path) -> SelfHealingVectorDb {
+    let mut engine = SelfHealingVectorDb::new(config(dir), None).expect("engine created");
+    let ids: Vec<i64> = (0..1500).map(|i| i * 2).collect();
+    let vectors: Vec<f32> = ids.iter().flat_map(|&id| [id as f32, 0.5]).collect();
+    let payloads: Vec<Value> = ids
+        .iter()
+        .map(|&id| {
+            if id % 10 == 0 {
+                json!({ "id": id })
+            } else {
+                Value::Null
+            }
+        })
+        .collect();
+    engine
+        .add_vectors_with_payloads(&ids, &vectors, &payloads)
+        .expect("add");
+    engine
+}
+
+fn exported(engine: &SelfHealingVectorDb, format: ExportFormat) -> Vec<ImportRecord> {
+    let mut out = Vec::new();
+    let report = engine.export(format, &mut out).expect("export");
+    assert_eq!(report.exported, 1500);
+    let format = match format {
+        ExportFormat::Jsonl => ImportFormat::Jsonl,
+        ExportFormat::Npy => ImportFormat::Npy,
+        ExportFormat::Fvecs => ImportFormat::Fvecs,
+    };
+    import::read(Cursor::new(out), format, 0)
+        .expect("readable")
+        .collect::<Result<_, _>>()
+        .expect("records")
+}
+
+#[test]
+fn every_format_reads_back_with_the_importer() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let engine = engine(tmp_dir.path());
+
+    let records = exported(&engine, ExportFormat::Jsonl);
+    assert_eq!(records.len(), 1500);
+    assert_eq!(
+        (records[1234].id, &records[1234].vector),
+        (2468, &vec![2468.0, 0.5])
+    );
+    assert_eq!(records[5].payload, json!({ "id": 10 }));
+    assert_eq!(records[6].payload, Value::Null);
+
+    // The binary formats hold the vectors alone, in id order.
+    for format in [ExportFormat::Npy, ExportFormat::Fvecs] {
+        let records = exported(&engine, format);
+        assert_eq!(records.len(), 1500);
+        assert_eq!(records[1499].vector, vec![2998.0, 0.5]);
+    }
+}
+
+#[test]
+fn an_exported_file_migrates_into_another_store() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let (source_dir, target_dir) = (tmp_dir.path().join("a"), tmp_dir.path().join("b"));
+    std::fs::create_dir(&source_dir).expect("source dir");
+    std::fs::create_dir(&target_dir).expect("target dir");
+    let source = engine(&source_dir);
+    let path = tmp_dir.path().join("vectors.jsonl");
+    source.export_file(&path, None).expect("export");
+
+    let mut target = SelfHealingVectorDb::new(config(&target_dir), None).expect("engine created");
+    let report = target
+        .import_file(&path, &ImportOptions::default())
+        .expect("import");
+    assert_eq!(report.imported, 1500);
+
+    let ids = [0, 10, 1998, 2998];
+    let rows = |engine: &SelfHealingVectorDb| json!(engine.get(&ids).expect("get"));
+    assert_eq!(rows(&target), rows(&source));
+    assert!(source
+        .export_file(&tmp_dir.path().join("vectors.csv"), None)
+        .is_err());
+}
+
+#[test]
+fn an_export_snapshot_is_unaffected_by_later_writes() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+
+    let snapshot = engine.export_snapshot().expect("snapshot");
+    engine
+        .add_vectors(&[1], &[1.0, 0.5])
+        .expect("add after the snapshot");
+    engine
+        .delete_vectors(&[0, 2])
+        .expect("delete after the snapshot");
+
+    let mut out = Vec::new();
+    let report = snapshot
+        .write(ExportFormat::Jsonl, &mut out)
+        .expect("export");
+    assert_eq!(report.exported, 1500);
+    assert!(String::from_utf8(out).unwrap().starts_with("{\"id\":0,"));
+
+    drop(snapshot);
+    let files: Vec<_> = std::fs::read_dir(tmp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec!["vectors.sqlite"]);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::engine::SearchParams;
+use self_healing_vector_db::filter::Filter;
+use self_healing_vector_db::{EngineConfig, Metric, SelfHealingVectorDb};
+use serde_json::json;
+use tempfile::tempdir;
+
+#[test]
+fn filtered_search_returns_k_matching_vectors() {
+    let dim = 2;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        hnsw_ef_search: 8,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+
+    // 100 vectors on a line; only every tenth one belongs to tenant "b".
+    let ids: Vec<i64> = (0..100).collect();
+    let vectors: Vec<f32> = ids.iter().flat_map(|&id| [id as f32, 0.0]).collect();
+    let payloads: Vec<_> = ids
+        .iter()
+        .map(|&id| json!({ "tenant": if id % 10 == 9 { "b" } else { "a" } }))
+        .collect();
+    engine
+        .add_vectors_with_payloads(&ids, &vectors, &payloads)
+        .expect("add should succeed");
+
+    let params = SearchParams {
+        with_payload: true,
+        filter: Some(Filter::Eq {
+            field: "tenant".into(),
+            value: json!("b"),
+        }),
+        ..Default::default()
+    };
+    let results = engine
+        .search_with(&[0.0, 0.0], 3, &params)
+        .expect("search should succeed");
+
+    let returned: Vec<usize> = results.iter().map(|r| r.id).collect();
+    assert_eq!(returned, vec![9, 19, 29]);
+    assert!(results
+        .iter()
+        .all(|r| r.payload == Some(json!({ "tenant": "b" }))));
+}
+
+#[test]
+fn broad_filters_search_the_graph_and_selective_ones_score_matches_exactly() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 20_000,
+        mutable_segment_capacity: 20_000,
+        hnsw_ef_construction: 64,
+        ..test_config(tmp_dir.path())
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+
+    // Scattered points; more rows match tenant "a" than a filtered search
+    // scores exactly.
+    let point = |id: i64| {
+        let h = (id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        [
+            (h >> 40) as f32 / 16_777_216.0,
+            (h >> 16 & 0xFF_FFFF) as f32 / 16_777_216.0,
+        ]
+    };
+    let tenant = |id: i64| if id % 10 == 0 { "b" } else { "a" };
+    let ids: Vec<i64> = (0..20_000).collect();
+    let vectors: Vec<f32> = ids.iter().flat_map(|&id| point(id)).collect();
+    let payloads: Vec<_> = ids
+        .iter()
+        .map(|&id| json!({ "tenant": tenant(id) }))
+        .collect();
+    engine
+        .add_vectors_with_payloads(&ids, &vectors, &payloads)
+        .expect("add should succeed");
+
+    let query = [0.5, 0.5];
+    for name in ["a", "b"] {
+        let params = SearchParams {
+            filter: Some(Filter::Eq {
+                field: "tenant".into(),
+                value: json!(name),
+            }),
+            ..Default::default()
+        };
+        let returned: Vec<usize> = engine
+            .search_with(&query, 3, &params)
+            .expect("search should succeed")
+            .iter()
+            .map(|r| r.id)
+            .collect();
+
+        let mut expected: Vec<(f32, i64)> = ids
+            .iter()
+            .filter(|&&id| tenant(id) == name)
+            .map(|&id| (Metric::L2.graph_distance(&query, &point(id)), id))
+            .collect();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let expected: Vec<usize> = expected.iter().map(|&(_, id)| id as usize).collect();
+        if name == "b" {
+            // Few enough matches to be scored exactly.
+            assert_eq!(returned, expected[..3], "tenant {name}");
+        } else {
+            // The graph search is approximate, but only returns matches.
+            assert_eq!(returned.len(), 3);
+            assert!(
+                returned.iter().all(|id| expected[..20].contains(id)),
+                "tenant {name}: {returned:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+#[test]
+fn get_returns_stored_vectors_and_skips_missing_ids() {
+    let dim = 4;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    engine
+        .add_vectors(&[1, 2], &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+        .expect("add_vectors should succeed");
+    engine.delete_vectors(&[2]).expect("delete should succeed");
+
+    let found = engine.get(&[1, 2, 3]).expect("get should succeed");
+    assert_eq!(found.len(), 1, "deleted and unknown ids are omitted");
+    assert_eq!(found[0].id, 1);
+    assert_eq!(found[0].vector, vec![1.0, 0.0, 0.0, 0.0]);
+}
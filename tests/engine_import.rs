@@ -0,0 +1,88 @@
+mod common;
+
+use std::path::Path;
+
+use common::test_config;
+use self_healing_vector_db::import::{ImportFormat, ImportOptions};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde_json::json;
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 16,
+        mutable_segment_capacity: 16,
+        ..test_config(dir)
+    }
+}
+
+#[test]
+fn fvecs_files_are_imported_in_batches_and_indexed() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("engine created");
+    engine.add_vectors(&[1000], &[-1.0, -1.0]).expect("add");
+
+    let path = tmp_dir.path().join("base.fvecs");
+    let mut bytes = Vec::new();
+    for i in 0..25 {
+        bytes.extend(2_i32.to_le_bytes());
+        bytes.extend(
+            [i as f32, (i % 5) as f32]
+                .iter()
+                .flat_map(|x| x.to_le_bytes()),
+        );
+    }
+    std::fs::write(&path, bytes).expect("write");
+
+    let options = ImportOptions {
+        first_id: 100,
+        batch_size: 10,
+        ..ImportOptions::default()
+    };
+    let report = engine.import_file(&path, &options).expect("import");
+    assert_eq!((report.imported, report.batches), (25, 3));
+
+    assert_eq!(engine.health().size, 26);
+    assert_eq!(engine.get(&[124]).expect("get")[0].vector, vec![24.0, 4.0]);
+    let results = engine.search(&[17.0, 2.0], 1).expect("search");
+    assert_eq!(results[0].id, 117);
+    assert!(engine.repair().expect("repair").is_consistent());
+}
+
+#[test]
+fn json_lines_keep_payloads_and_stop_at_the_first_bad_record() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("engine created");
+
+    let path = tmp_dir.path().join("vectors.data");
+    let mut lines: Vec<String> = (0..12)
+        .map(|i| json!({ "id": i, "vector": [i, 1], "payload": { "n": i } }).to_string())
+        .collect();
+    lines.push(json!({ "id": 12, "vector": [1, 2, 3] }).to_string());
+    std::fs::write(&path, lines.join("\n")).expect("write");
+
+    // The extension does not name a format.
+    assert!(engine
+        .import_file(&path, &ImportOptions::default())
+        .is_err());
+
+    let options = ImportOptions {
+        format: Some(ImportFormat::Jsonl),
+        batch_size: 5,
+        ..ImportOptions::default()
+    };
+    let err = engine.import_file(&path, &options).unwrap_err();
+    assert!(err.to_string().contains("record 12"), "{err}");
+
+    // Whole batches before the bad record were imported and indexed.
+    assert_eq!(engine.health().size, 10);
+    assert_eq!(
+        engine.get(&[7]).expect("get")[0].payload,
+        Some(json!({ "n": 7 }))
+    );
+    assert!(engine.get(&[10]).expect("get").is_empty());
+    assert!(engine.repair().expect("repair").is_consistent());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::engine::EngineError;
+use self_healing_vector_db::{EngineConfig, Metric, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+#[test]
+fn metric_is_persisted_and_cannot_silently_change() {
+    let dim = 2;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        metric: Metric::Cosine,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    engine
+        .add_vectors(&[1, 2], &[10.0, 0.0, 0.5, 0.5])
+        .expect("add_vectors should succeed");
+
+    let results = engine
+        .search(&[1.0, 0.1], 2)
+        .expect("search should succeed");
+    assert_eq!(results[0].id, 1, "cosine ignores vector length");
+    assert!(results[0].distance < 0.01);
+    drop(engine);
+
+    let l2 = EngineConfig {
+        metric: Metric::L2,
+        ..cfg.clone()
+    };
+    match SelfHealingVectorDb::new(l2, None) {
+        Err(EngineError::ConfigMismatch {
+            key,
+            stored,
+            configured,
+        }) => {
+            assert_eq!(key, "metric");
+            assert_eq!(stored, "cosine");
+            assert_eq!(configured, "l2");
+        }
+        Err(other) => panic!("unexpected error: {other}"),
+        Ok(_) => panic!("reopening with a different metric must fail"),
+    }
+
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+    assert_eq!(engine.health().size, 2);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::engine::SearchParams;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde_json::json;
+use tempfile::tempdir;
+
+#[test]
+fn payloads_are_stored_and_returned_on_request() {
+    let dim = 4;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+
+    let doc = json!({ "doc_id": "a-1", "source": "https://example.com/a" });
+    engine
+        .add_vectors_with_payloads(
+            &[1, 2],
+            &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            &[doc.clone(), serde_json::Value::Null],
+        )
+        .expect("add should succeed");
+
+    let plain = engine
+        .search(&[1.0, 0.0, 0.0, 0.0], 1)
+        .expect("search should succeed");
+    assert_eq!(plain[0].id, 1);
+    assert!(plain[0].payload.is_none(), "payloads are opt-in");
+
+    drop(engine);
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine recreated");
+
+    let params = SearchParams {
+        with_payload: true,
+        ..Default::default()
+    };
+    let results = engine
+        .search_with(&[1.0, 0.0, 0.0, 0.0], 2, &params)
+        .expect("search should succeed");
+    assert_eq!(results[0].id, 1);
+    assert_eq!(results[0].payload, Some(doc.clone()));
+    assert_eq!(results[1].payload, None);
+
+    let fetched = engine.get(&[1]).expect("get should succeed");
+    assert_eq!(fetched[0].payload, Some(doc));
+}
+
+#[test]
+fn payload_count_must_match_ids() {
+    let dim = 2;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let res = engine.add_vectors_with_payloads(&[1, 2], &[1.0, 0.0, 0.0, 1.0], &[json!({})]);
+    assert!(res.is_err());
+    assert_eq!(engine.health().size, 0);
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use std::time::Duration;
+
+use common::test_config;
+use self_healing_vector_db::engine::EngineError;
+use self_healing_vector_db::rebuild::{RebuildParams, RebuildState};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde_json::json;
+use tempfile::tempdir;
+
+fn finish(engine: &mut SelfHealingVectorDb) {
+    for _ in 0..500 {
+        if engine.finish_rebuild().expect("rebuild succeeds") {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("rebuild did not finish");
+}
+
+#[test]
+fn background_rebuild_swaps_in_a_new_index_with_concurrent_writes() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = test_config(tmp_dir.path());
+
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    assert_eq!(engine.rebuild_status().state, RebuildState::Idle);
+
+    let ids: Vec<i64> = (0..200).collect();
+    let flat: Vec<f32> = (0..200).flat_map(|i| [i as f32, 0.0, 0.0, 0.0]).collect();
+    engine.add_vectors(&ids, &flat).expect("add_vectors");
+
+    let params = RebuildParams {
+        hnsw_m: Some(8),
+        hnsw_ef_search: Some(32),
+        ..RebuildParams::default()
+    };
+    let status = engine.start_rebuild(&params).expect("rebuild started");
+    assert_eq!(status.state, RebuildState::Running);
+    assert!(matches!(
+        engine.start_rebuild(&params),
+        Err(EngineError::RebuildInProgress)
+    ));
+
+    // Served by the live index and buffered for the new one.
+    engine
+        .add_vectors(&[500], &[0.0, 50.0, 0.0, 0.0])
+        .expect("add during rebuild");
+    engine.delete_vectors(&[10]).expect("delete during rebuild");
+    assert_eq!(engine.health().size, 200);
+    assert_eq!(engine.rebuild_status().pending_writes, 2);
+
+    finish(&mut engine);
+
+    let status = engine.rebuild_status();
+    assert_eq!(status.state, RebuildState::Completed);
+    assert_eq!(status.total, status.inserted);
+    assert_eq!(status.pending_writes, 2);
+    assert!(!engine.is_rebuilding());
+
+    // The new index holds exactly what storage holds, including id 500.
+    assert_eq!(engine.health().size, 200);
+    let results = engine.search(&[10.0, 0.0, 0.0, 0.0], 3).expect("search");
+    assert!(results.iter().all(|r| r.id != 10));
+    assert!(engine.check_consistency().expect("check").is_consistent());
+
+    // Writes after the swap go to the new index.
+    engine
+        .add_vectors(&[600], &[0.0, 0.0, 60.0, 0.0])
+        .expect("add");
+    assert_eq!(engine.health().size, 201);
+    assert!(engine.check_consistency().expect("check").is_consistent());
+}
+
+#[test]
+fn invalid_rebuild_parameters_are_rejected() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        hnsw_max_elements: 100,
+        mutable_segment_capacity: 100,
+        ..test_config(tmp_dir.path())
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+
+    let params = RebuildParams {
+        hnsw_m: Some(0),
+        ..RebuildParams::default()
+    };
+    assert!(matches!(
+        engine.start_rebuild(&params),
+        Err(EngineError::InvalidParameter(_))
+    ));
+    assert!(!engine.is_rebuilding());
+    assert!(!engine.finish_rebuild().expect("nothing to finish"));
+}
+
+#[test]
+fn writes_do_not_wait_for_a_background_build_to_read_storage() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        hnsw_max_elements: 100_000,
+        mutable_segment_capacity: 100_000,
+        hnsw_m: 4,
+        hnsw_ef_construction: 8,
+        hnsw_ef_search: 8,
+        ..test_config(tmp_dir.path())
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+
+    // Large enough that reading it all in one transaction takes several
+    // times longer than the busy timeout below.
+    let n = 20_000;
+    let ids: Vec<i64> = (0..n).collect();
+    for chunk in ids.chunks(1_000) {
+        let flat: Vec<f32> = chunk
+            .iter()
+            .flat_map(|&i| [i as f32, 0.0, 0.0, 0.0])
+            .collect();
+        let payloads: Vec<_> = chunk.iter().map(|_| json!("x".repeat(2_000))).collect();
+        engine
+            .add_vectors_with_payloads(chunk, &flat, &payloads)
+            .expect("add_vectors");
+    }
+
+    // A second writer on the same file that gives up after 100 ms, where
+    // the engine's own connection would wait out SQLite's 5 s default.
+    let other = rusqlite::Connection::open(&cfg.storage_path).expect("open");
+    other
+        .busy_timeout(Duration::from_millis(100))
+        .expect("busy timeout");
+    other
+        .execute("CREATE TABLE scratch (n INTEGER);", [])
+        .expect("scratch table");
+
+    let write = |n: i64| {
+        other
+            .execute("INSERT INTO scratch VALUES (?1);", [n])
+            .expect("write during the build");
+    };
+
+    engine
+        .start_rebuild(&RebuildParams::default())
+        .expect("rebuild started");
+    // Only the other writer until the build starts inserting, which is when
+    // it has read at least its first page.
+    let mut written = 0;
+    while engine.rebuild_status().inserted == 0 && !engine.rebuild_ready() {
+        write(written);
+        written += 1;
+    }
+    // Writes also swap in the new index once it is ready.
+    let mut added = 0;
+    while engine.is_rebuilding() {
+        write(written + added);
+        let id = n + added;
+        engine
+            .add_vectors(&[id], &[id as f32, 1.0, 0.0, 0.0])
+            .expect("add during the build");
+        added += 1;
+    }
+
+    assert_eq!(engine.rebuild_status().state, RebuildState::Completed);
+    assert_eq!(engine.health().size, (n + added) as usize);
+    assert!(engine.verify().expect("verify").is_consistent());
+}
+
+#[test]
+fn rebuilt_parameters_outlive_a_restart_until_the_configuration_changes() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        hnsw_max_elements: 1_000,
+        mutable_segment_capacity: 1_000,
+        ..test_config(tmp_dir.path())
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let ids: Vec<i64> = (0..100).collect();
+    let flat: Vec<f32> = (0..100).flat_map(|i| [i as f32, 0.0, 0.0, 0.0]).collect();
+    engine.add_vectors(&ids, &flat).expect("add_vectors");
+
+    let params = RebuildParams {
+        hnsw_m: Some(8),
+        hnsw_ef_construction: Some(50),
+        ..RebuildParams::default()
+    };
+    engine.start_rebuild(&params).expect("rebuild started");
+    finish(&mut engine);
+    assert!(engine.snapshot().expect("snapshot").is_some());
+    drop(engine);
+
+    // The snapshot, built with m = 8, is loaded rather than rebuilt: there
+    // is nothing new to save.
+    let engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine reopened");
+    assert_eq!(engine.snapshot().expect("snapshot"), None);
+    assert_eq!(engine.health().size, 100);
+    drop(engine);
+
+    // A changed configuration takes over, and a later return to the old one
+    // does not bring the rebuild's parameters back.
+    for hnsw_m in [12, 16] {
+        let cfg = EngineConfig {
+            hnsw_m,
+            ..cfg.clone()
+        };
+        let engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine reopened");
+        assert!(engine.snapshot().expect("snapshot").is_some());
+        drop(engine);
+        let engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+        assert_eq!(engine.snapshot().expect("snapshot"), None);
+        assert_eq!(engine.health().size, 100);
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::engine::{EngineError, RecallParams};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+/// Deterministic pseudo-random vectors in [0, 1).
+fn random_vectors(n: usize, dim: usize) -> Vec<f32> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..n * dim)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        })
+        .collect()
+}
+
+fn engine_with(dir: &std::path::Path, m: usize, ef: usize) -> SelfHealingVectorDb {
+    let dim = 16;
+    let cfg = EngineConfig {
+        dim,
+        hnsw_m: m,
+        hnsw_ef_construction: ef,
+        hnsw_ef_search: ef,
+        ..test_config(dir)
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let ids: Vec<i64> = (0..1_000).collect();
+    engine
+        .add_vectors(&ids, &random_vectors(ids.len(), dim))
+        .expect("add_vectors");
+    engine
+}
+
+#[test]
+fn well_built_index_has_high_recall() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let engine = engine_with(tmp_dir.path(), 16, 200);
+
+    let report = engine
+        .estimate_recall(&RecallParams::default())
+        .expect("estimate_recall");
+    assert_eq!(report.k, 10);
+    assert_eq!(report.queries, 32);
+    assert!(report.recall >= 0.9, "{report:?}");
+
+    let health = engine.health();
+    assert!(health.ok);
+    assert_eq!(health.recall, Some(report));
+}
+
+#[test]
+fn low_recall_is_reported_as_degraded() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine_with(tmp_dir.path(), 2, 2);
+
+    let params = RecallParams {
+        k: 50,
+        ..RecallParams::default()
+    };
+    let report = engine.estimate_recall(&params).expect("estimate_recall");
+    assert!(report.is_degraded(), "{report:?}");
+
+    let health = engine.health();
+    assert!(!health.ok);
+    assert_eq!(health.reason, "recall-degraded");
+
+    // A later failed check is counted but does not replace the first reason.
+    let conn = rusqlite::Connection::open(tmp_dir.path().join("vectors.sqlite")).expect("open");
+    conn.execute("UPDATE vectors SET vector = zeroblob(64) WHERE id = 3;", [])
+        .expect("corrupt");
+    assert_eq!(engine.scrub().expect("scrub").quarantined, vec![3]);
+    let health = engine.health();
+    assert!(!health.ok);
+    assert_eq!(health.reason, "recall-degraded");
+    assert_eq!(health.quarantined, 1);
+}
+
+#[test]
+fn empty_index_has_perfect_recall() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        hnsw_max_elements: 100,
+        mutable_segment_capacity: 100,
+        ..test_config(tmp_dir.path())
+    };
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let report = engine
+        .estimate_recall(&RecallParams::default())
+        .expect("estimate_recall");
+    assert_eq!(report.queries, 0);
+    assert_eq!(report.recall, 1.0);
+}
+
+#[test]
+fn zero_k_is_rejected() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let engine = engine_with(tmp_dir.path(), 16, 200);
+
+    let params = RecallParams {
+        k: 0,
+        ..RecallParams::default()
+    };
+    let err = engine.estimate_recall(&params).expect_err("k = 0");
+    assert!(matches!(err, EngineError::InvalidParameter(_)), "{err}");
+    assert_eq!(engine.health().recall, None);
+}
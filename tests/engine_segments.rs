@@ -0,0 +1,178 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use common::test_config;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+fn config(dir: &Path, max_elements: usize) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: max_elements,
+        mutable_segment_capacity: max_elements,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64], offset: f32) -> Vec<f32> {
+    ids.iter().flat_map(|&id| [id as f32, offset]).collect()
+}
+
+fn compact(engine: &mut SelfHealingVectorDb) {
+    for _ in 0..500 {
+        engine.maintain();
+        if !engine.is_compacting() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("compaction did not finish");
+}
+
+#[test]
+fn full_segments_are_sealed_and_their_snapshots_reused() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = config(tmp_dir.path(), 4);
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+
+    let ids: Vec<i64> = (0..10).collect();
+    engine.add_vectors(&ids, &vectors(&ids, 0.0)).expect("add");
+    let health = engine.health();
+    assert!(health.ok);
+    assert_eq!((health.size, health.slots, health.segments), (10, 10, 3));
+    assert!(!engine.is_rebuilding());
+    assert!(!engine.is_compacting());
+
+    let info = engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    assert_eq!((info.points, info.segments, info.written), (10, 3, 3));
+
+    // Only the mutable segment changed, so only it is written again.
+    engine.add_vectors(&[10], &[10.0, 0.0]).expect("add");
+    let info = engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    assert_eq!((info.points, info.segments, info.written), (11, 3, 1));
+
+    // A delete from a sealed segment only changes its id map.
+    engine.delete_vectors(&[1]).expect("delete");
+    let info = engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    assert_eq!((info.points, info.written), (10, 1));
+    drop(engine);
+
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+    let health = engine.health();
+    assert_eq!((health.size, health.segments), (10, 3));
+    assert!(engine.check_consistency().expect("check").is_consistent());
+    let results = engine.search(&[1.0, 0.0], 10).expect("search");
+    assert!(results.iter().all(|r| r.id != 1));
+}
+
+#[test]
+fn compaction_merges_sparse_segments_and_purges_deleted_vectors() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path(), 4), None).expect("engine created");
+
+    let ids: Vec<i64> = (0..12).collect();
+    engine.add_vectors(&ids, &vectors(&ids, 0.0)).expect("add");
+    engine.delete_vectors(&[0, 1, 2, 4, 5, 6]).expect("delete");
+    compact(&mut engine);
+
+    let health = engine.health();
+    assert_eq!((health.size, health.slots, health.segments), (6, 6, 2));
+    assert!(engine.check_consistency().expect("check").is_consistent());
+    let results = engine.search(&[0.0, 0.0], 2).expect("search");
+    assert_eq!(results.iter().map(|r| r.id).collect::<Vec<_>>(), vec![3, 7]);
+}
+
+#[test]
+fn slots_held_by_updates_are_reclaimed_by_compaction() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path(), 10), None).expect("engine created");
+
+    let ids: Vec<i64> = (0..4).collect();
+    for round in 0..3 {
+        engine
+            .add_vectors(&ids, &vectors(&ids, round as f32))
+            .expect("add");
+    }
+    compact(&mut engine);
+
+    let health = engine.health();
+    assert_eq!(health.size, 4);
+    assert_eq!((health.slots, health.max_elements), (4, 10));
+    assert!(health.warnings.is_empty());
+    assert_eq!(engine.search(&[2.0, 2.0], 1).expect("search")[0].id, 2);
+}
+
+#[test]
+fn inserts_past_max_elements_seal_segments_and_stay_searchable() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        mutable_segment_capacity: 4,
+        ..config(tmp_dir.path(), 10)
+    };
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+
+    let ids: Vec<i64> = (0..9).collect();
+    engine.add_vectors(&ids, &vectors(&ids, 0.0)).expect("add");
+    let health = engine.health();
+    assert!(health.ok);
+    assert_eq!(
+        (
+            health.max_elements,
+            health.mutable_segment_capacity,
+            health.segments
+        ),
+        (10, 4, 3)
+    );
+    assert!(health.warnings.is_empty(), "{:?}", health.warnings);
+
+    // Far past `max_elements`: no rebuild, just more segments.
+    let more: Vec<i64> = (9..30).collect();
+    engine
+        .add_vectors(&more, &vectors(&more, 0.0))
+        .expect("add");
+    assert!(!engine.is_rebuilding());
+    assert_eq!(engine.health().size, 30);
+    for id in 0..30 {
+        let results = engine.search(&[id as f32, 0.0], 1).expect("search");
+        assert_eq!(results[0].id, id as usize);
+    }
+    assert!(engine.check_consistency().expect("check").is_consistent());
+    drop(engine);
+
+    // Without a snapshot, reopening rebuilds from storage into full segments.
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+    let health = engine.health();
+    assert_eq!((health.size, health.segments), (30, 3));
+    assert_eq!(engine.search(&[17.2, 0.0], 1).expect("search")[0].id, 17);
+}
+
+#[test]
+fn health_warns_when_the_index_spans_many_segments() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path(), 1), None).expect("engine created");
+
+    let ids: Vec<i64> = (0..70).collect();
+    engine.add_vectors(&ids, &vectors(&ids, 0.0)).expect("add");
+    let health = engine.health();
+    assert!(health.ok);
+    assert_eq!(health.segments, 70);
+    assert!(
+        health.warnings.iter().any(|w| w.contains("70 segments")),
+        "{:?}",
+        health.warnings
+    );
+}
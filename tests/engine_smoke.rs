@@ -1,5 +1,6 @@
-use std::path::PathBuf;
+mod common;
 
+use common::test_config;
 use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
 use tempfile::tempdir;
 
@@ -24,29 +25,20 @@ fn make_flat_vectors(dim: usize) -> (Vec<i64>, Vec<f32>, Vec<f32>) {
 fn basic_add_search_and_self_heal() {
     let dim = 384;
     let tmp_dir = tempdir().expect("tempdir");
-    let db_path: PathBuf = tmp_dir.path().join("vectors.sqlite");
-
     let cfg = EngineConfig {
         dim,
-        storage_path: db_path.clone(),
-        hnsw_max_elements: 10_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
+        ..test_config(tmp_dir.path())
     };
 
     let (ids, flat, query_vec) = make_flat_vectors(dim);
 
     // First engine: add and search.
-    let mut engine =
-        SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
     engine
         .add_vectors(&ids, &flat)
         .expect("add_vectors should succeed");
 
-    let results = engine
-        .search(&query_vec, 2)
-        .expect("search should succeed");
+    let results = engine.search(&query_vec, 2).expect("search should succeed");
     assert!(!results.is_empty(), "expected at least one neighbor");
     assert_eq!(results[0].id, 1, "nearest neighbor should be id 1");
 
@@ -57,8 +49,7 @@ fn basic_add_search_and_self_heal() {
     drop(engine);
 
     // New engine bootstraps from the same SQLite file (self-healing).
-    let engine2 =
-        SelfHealingVectorDb::new(cfg, None).expect("engine recreated");
+    let engine2 = SelfHealingVectorDb::new(cfg, None).expect("engine recreated");
     let health2 = engine2.health();
     assert_eq!(
         health2.size, 2,
@@ -68,8 +59,12 @@ fn basic_add_search_and_self_heal() {
     let results2 = engine2
         .search(&query_vec, 2)
         .expect("search after restart should succeed");
-    assert!(!results2.is_empty(), "expected at least one neighbor after restart");
-    assert_eq!(results2[0].id, 1, "nearest neighbor after restart should still be id 1");
+    assert!(
+        !results2.is_empty(),
+        "expected at least one neighbor after restart"
+    );
+    assert_eq!(
+        results2[0].id, 1,
+        "nearest neighbor after restart should still be id 1"
+    );
 }
-
-
@@ -0,0 +1,92 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::snapshot::snapshot_dir;
+use self_healing_vector_db::SelfHealingVectorDb;
+use tempfile::tempdir;
+
+fn unit(i: usize) -> Vec<f32> {
+    let mut v = vec![0.0; 4];
+    v[i % 4] = 1.0 + (i / 4) as f32;
+    v
+}
+
+#[test]
+fn restart_loads_the_snapshot_and_replays_later_writes() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = test_config(tmp_dir.path());
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let ids: Vec<i64> = (0..20).collect();
+    let flat: Vec<f32> = (0..20).flat_map(unit).collect();
+    engine.add_vectors(&ids, &flat).expect("add_vectors");
+
+    let info = engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    assert_eq!(info.points, 20);
+    assert!(
+        engine.snapshot().expect("snapshot").is_none(),
+        "nothing changed"
+    );
+
+    // Written after the snapshot: must be replayed on the next start.
+    engine.delete_vectors(&[0, 5]).expect("delete");
+    engine
+        .add_vectors(&[1], &[0.0, 0.0, 0.0, 9.0])
+        .expect("update");
+    engine
+        .add_vectors(&[100], &[7.0, 7.0, 7.0, 7.0])
+        .expect("insert");
+    drop(engine);
+
+    assert!(snapshot_dir(&cfg.storage_path)
+        .join("manifest.json")
+        .exists());
+
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine reopened");
+    assert_eq!(engine.health().size, 19);
+
+    let results = engine.search(&[7.0, 7.0, 7.0, 7.0], 1).expect("search");
+    assert_eq!(results[0].id, 100);
+    let results = engine.search(&[0.0, 0.0, 0.0, 9.0], 1).expect("search");
+    assert_eq!(results[0].id, 1);
+    let results = engine.search(&unit(0), 20).expect("search");
+    assert!(results.iter().all(|r| r.id != 0 && r.id != 5));
+}
+
+#[test]
+fn corrupt_snapshot_falls_back_to_a_full_rebuild() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = test_config(tmp_dir.path());
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+    let ids: Vec<i64> = (0..10).collect();
+    let flat: Vec<f32> = (0..10).flat_map(unit).collect();
+    engine.add_vectors(&ids, &flat).expect("add_vectors");
+    engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    drop(engine);
+
+    let dir = snapshot_dir(&cfg.storage_path);
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "data") {
+            let mut bytes = std::fs::read(&path).unwrap();
+            let mid = bytes.len() / 2;
+            bytes[mid] ^= 0xff;
+            std::fs::write(&path, bytes).unwrap();
+        }
+    }
+
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine rebuilt");
+    assert_eq!(engine.health().size, 10);
+    let results = engine.search(&unit(6), 1).expect("search");
+    assert_eq!(results[0].id, 6);
+
+    // The next snapshot replaces the corrupt one.
+    assert!(engine.snapshot().expect("snapshot").is_some());
+}
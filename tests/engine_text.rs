@@ -0,0 +1,137 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use common::test_config;
+use self_healing_vector_db::embeddings::{DummyEmbedder, Embedder, EmbeddingError};
+use self_healing_vector_db::engine::{EngineError, SearchParams};
+use self_healing_vector_db::{EngineConfig, Metric, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+/// Counts a few marker letters so that similar texts get similar vectors.
+struct LetterEmbedder;
+
+impl Embedder for LetterEmbedder {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        Ok(texts
+            .iter()
+            .map(|t| {
+                ['a', 'e', 'o', 'u']
+                    .iter()
+                    .map(|c| t.matches(*c).count() as f32)
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        metric: Metric::Cosine,
+        ..test_config(dir)
+    }
+}
+
+#[test]
+fn add_texts_and_search_text_use_the_embedder() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path()), Some(Arc::new(LetterEmbedder)))
+            .expect("engine created");
+
+    let texts = vec!["banana bread".to_string(), "good food".to_string()];
+    engine
+        .add_texts(&[1, 2], &texts, &[])
+        .expect("add_texts should succeed");
+
+    let results = engine
+        .search_text("a bandana", 1)
+        .expect("search_text should succeed");
+    assert_eq!(results[0].id, 1);
+    assert!(results[0].text.is_none(), "source text is opt-in");
+
+    let params = SearchParams {
+        with_text: true,
+        ..Default::default()
+    };
+    let results = engine
+        .search_text_with("too cool", 1, &params)
+        .expect("search_text should succeed");
+    assert_eq!(results[0].id, 2);
+    assert_eq!(results[0].text.as_deref(), Some("good food"));
+
+    let stored = engine.get(&[1]).expect("get should succeed");
+    assert_eq!(stored[0].text.as_deref(), Some("banana bread"));
+}
+
+#[test]
+fn text_operations_without_embedder_fail() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("engine created");
+
+    let err = engine
+        .add_texts(&[1], &["hello".to_string()], &[])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        EngineError::Embedding(EmbeddingError::NotConfigured)
+    ));
+    assert!(engine.search_text("hello", 1).is_err());
+    assert_eq!(engine.health().size, 0);
+}
+
+#[test]
+fn embedder_with_a_different_dim_is_rejected_on_open() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let err = SelfHealingVectorDb::new(
+        config(tmp_dir.path()),
+        Some(Arc::new(DummyEmbedder { dim: 3 })),
+    )
+    .err()
+    .expect("open should fail");
+    assert!(matches!(
+        err,
+        EngineError::Embedding(EmbeddingError::DimMismatch {
+            expected: 4,
+            got: 3
+        })
+    ));
+}
+
+#[test]
+fn texts_embedded_apart_from_the_engine_can_be_added() {
+    let tmp_dir = tempdir().expect("tempdir");
+
+    let mut engine =
+        SelfHealingVectorDb::new(config(tmp_dir.path()), Some(Arc::new(LetterEmbedder)))
+            .expect("engine created");
+
+    let embedder = engine.text_embedder();
+    let texts = vec!["banana bread".to_string(), "good food".to_string()];
+    let vectors = embedder.embed(&texts).expect("embed should succeed");
+    assert_eq!(vectors.len(), 8);
+
+    let err = engine
+        .add_embedded_texts(&[1], &texts, &vectors, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        EngineError::LengthMismatch { field: "texts", .. }
+    ));
+    engine
+        .add_embedded_texts(&[1, 2], &texts, &vectors, &[])
+        .expect("add should succeed");
+
+    let query = embedder
+        .embed(&["too cool".to_string()])
+        .expect("embed should succeed");
+    assert_eq!(
+        engine.search(&query, 1).expect("search should succeed")[0].id,
+        2
+    );
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::test_config;
+use self_healing_vector_db::engine::WriteKind;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+#[test]
+fn re_adding_an_id_updates_instead_of_duplicating() {
+    let dim = 4;
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim,
+        ..test_config(tmp_dir.path())
+    };
+
+    let mut engine = SelfHealingVectorDb::new(cfg.clone(), None).expect("engine created");
+
+    let first = engine
+        .add_vectors(&[1, 2], &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+        .expect("initial add should succeed");
+    assert!(first.iter().all(|r| r.kind == WriteKind::Inserted));
+
+    // Move id 1 far away from its original position.
+    let second = engine
+        .add_vectors(&[1, 3], &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0])
+        .expect("update should succeed");
+    assert_eq!(second[0].id, 1);
+    assert_eq!(second[0].kind, WriteKind::Updated);
+    assert_eq!(second[1].kind, WriteKind::Inserted);
+
+    assert_eq!(engine.health().size, 3);
+
+    let results = engine
+        .search(&[1.0, 0.0, 0.0, 0.0], 10)
+        .expect("search should succeed");
+    assert_eq!(
+        results.len(),
+        3,
+        "stale vector must not show up as a duplicate"
+    );
+
+    let nearest = engine
+        .search(&[0.0, 0.0, 0.0, 1.0], 1)
+        .expect("search should succeed");
+    assert_eq!(nearest[0].id, 1);
+    assert!(
+        nearest[0].distance < 1e-6,
+        "id 1 should match its new vector"
+    );
+
+    drop(engine);
+
+    let engine2 = SelfHealingVectorDb::new(cfg, None).expect("engine recreated");
+    let nearest2 = engine2
+        .search(&[0.0, 0.0, 0.0, 1.0], 1)
+        .expect("search after restart should succeed");
+    assert_eq!(nearest2[0].id, 1);
+}
@@ -1,5 +1,6 @@
-use std::path::PathBuf;
+mod common;
 
+use common::test_config;
 use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
 use tempfile::tempdir;
 
@@ -7,19 +8,12 @@ use tempfile::tempdir;
 fn add_vectors_with_wrong_dim_fails_and_does_not_change_health() {
     let dim = 384;
     let tmp_dir = tempdir().expect("tempdir");
-    let db_path: PathBuf = tmp_dir.path().join("vectors.sqlite");
-
     let cfg = EngineConfig {
         dim,
-        storage_path: db_path.clone(),
-        hnsw_max_elements: 10_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
+        ..test_config(tmp_dir.path())
     };
 
-    let mut engine =
-        SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let mut engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
     let health_before = engine.health();
     assert_eq!(health_before.size, 0);
 
@@ -43,5 +37,3 @@ fn add_vectors_with_wrong_dim_fails_and_does_not_change_health() {
         "index size should remain 0 after failed add"
     );
 }
-
-
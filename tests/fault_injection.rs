@@ -0,0 +1,186 @@
+mod common;
+
+use std::path::Path;
+
+use common::test_config;
+use self_healing_vector_db::faults::{Fault, Faults};
+use self_healing_vector_db::snapshot::snapshot_dir;
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use tempfile::tempdir;
+
+fn config(dir: &Path) -> EngineConfig {
+    EngineConfig {
+        dim: 2,
+        hnsw_max_elements: 1_000,
+        mutable_segment_capacity: 1_000,
+        ..test_config(dir)
+    }
+}
+
+fn vectors(ids: &[i64]) -> Vec<f32> {
+    ids.iter()
+        .flat_map(|&id| [id as f32, (id % 5) as f32])
+        .collect()
+}
+
+/// An engine holding ids `0..10`.
+fn engine(dir: &Path) -> SelfHealingVectorDb {
+    let mut engine = SelfHealingVectorDb::new(config(dir), None).expect("engine created");
+    let ids: Vec<i64> = (0..10).collect();
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    engine
+}
+
+fn assert_consistent(engine: &mut SelfHealingVectorDb) {
+    let report = engine.repair().expect("repair");
+    assert!(report.is_consistent(), "{report:?}");
+}
+
+#[test]
+fn storage_errors_mid_transaction_fail_the_write_and_change_nothing() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+    let faults = Faults::new();
+    engine.set_faults(Some(faults.clone()));
+
+    faults.arm(Fault::StorageWrite, 1);
+    let ids: Vec<i64> = (10..20).collect();
+    let err = engine.add_vectors(&ids, &vectors(&ids)).unwrap_err();
+    assert!(err.to_string().contains("injected fault"), "{err}");
+    assert!(
+        engine.get(&ids).expect("get").is_empty(),
+        "half a batch was stored"
+    );
+    assert_eq!(engine.health().size, 10);
+    assert_consistent(&mut engine);
+
+    faults.arm(Fault::StorageWrite, 1);
+    assert!(engine.delete_vectors(&[1, 2, 3]).is_err());
+    assert_eq!(engine.get(&[1, 2, 3]).expect("get").len(), 3);
+    assert_consistent(&mut engine);
+
+    // Once the fault clears, the same writes succeed.
+    engine.add_vectors(&ids, &vectors(&ids)).expect("add");
+    assert_eq!(engine.delete_vectors(&[1, 2, 3]).expect("delete"), 3);
+    assert_eq!(engine.health().size, 17);
+    assert_consistent(&mut engine);
+
+    // The log matches storage, so a restart restores the same state.
+    engine.snapshot().expect("snapshot");
+    drop(engine);
+    let mut engine = SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("reopened");
+    assert_eq!(engine.health().size, 17);
+    assert_consistent(&mut engine);
+}
+
+#[test]
+fn a_panic_during_index_insert_is_recovered_from_storage() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+    let faults = Faults::new();
+    engine.set_faults(Some(faults.clone()));
+
+    faults.arm(Fault::IndexInsertPanic, 1);
+    let ids: Vec<i64> = (10..15).collect();
+    engine
+        .add_vectors(&ids, &vectors(&ids))
+        .expect("batch stored and indexed");
+    assert_eq!(
+        faults.remaining(Fault::IndexInsertPanic),
+        0,
+        "the fault fired"
+    );
+
+    assert_eq!(engine.health().size, 15);
+    assert_consistent(&mut engine);
+    let results = engine.search(&[14.0, 4.0], 1).expect("search");
+    assert_eq!(results[0].id, 14);
+
+    // The rebuilt index keeps working.
+    engine.add_vectors(&[15], &vectors(&[15])).expect("add");
+    assert_eq!(engine.health().size, 16);
+}
+
+#[test]
+fn an_index_error_after_the_batch_is_stored_is_recovered_from_storage() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+    let faults = Faults::new();
+    engine.set_faults(Some(faults.clone()));
+
+    faults.arm(Fault::IndexInsertError, 1);
+    let ids: Vec<i64> = (10..15).collect();
+    engine
+        .add_vectors(&ids, &vectors(&ids))
+        .expect("the batch is stored, so the write succeeds");
+    assert_eq!(
+        faults.remaining(Fault::IndexInsertError),
+        0,
+        "the fault fired"
+    );
+
+    assert_eq!(engine.health().size, 15);
+    assert_consistent(&mut engine);
+    let results = engine.search(&[12.0, 2.0], 1).expect("search");
+    assert_eq!(results[0].id, 12);
+}
+
+#[test]
+fn truncated_and_corrupt_blobs_are_reported_by_the_consistency_check() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+    let faults = Faults::new();
+    engine.set_faults(Some(faults.clone()));
+
+    faults.arm(Fault::TruncateBlob, 1);
+    engine.add_vectors(&[10], &vectors(&[10])).expect("add");
+    faults.arm(Fault::CorruptBlob, 1);
+    engine.add_vectors(&[11], &vectors(&[11])).expect("add");
+
+    // Neither row reads back as written; the check must say so.
+    let report = engine.check_consistency().expect("check");
+    assert_eq!((report.extra, report.stale), (1, 1), "{report:?}");
+    let health = engine.health();
+    assert!(!health.ok);
+    assert_eq!(health.reason, "index-inconsistent");
+
+    // Reopening must not trip over the malformed rows; the rebuild sets
+    // them aside in quarantine.
+    drop(engine);
+    let mut engine = SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("reopened");
+    let health = engine.health();
+    assert_eq!((health.size, health.quarantined), (10, 2));
+    assert_eq!(health.reason, "rows-quarantined");
+    assert_consistent(&mut engine);
+}
+
+#[test]
+fn a_missing_snapshot_file_falls_back_to_rebuilding_from_storage() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let mut engine = engine(tmp_dir.path());
+    engine
+        .snapshot()
+        .expect("snapshot")
+        .expect("snapshot written");
+    engine.add_vectors(&[10], &vectors(&[10])).expect("add");
+    drop(engine);
+
+    let dir = snapshot_dir(&config(tmp_dir.path()).storage_path);
+    for entry in std::fs::read_dir(&dir).expect("snapshot dir") {
+        let path = entry.expect("entry").path();
+        if path.extension().is_some_and(|ext| ext == "graph") {
+            std::fs::remove_file(path).expect("remove");
+        }
+    }
+
+    let mut engine = SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("reopened");
+    assert_eq!(engine.health().size, 11);
+    assert_consistent(&mut engine);
+
+    // Without any snapshot at all, the engine rebuilds as well.
+    drop(engine);
+    std::fs::remove_dir_all(&dir).ok();
+    let mut engine = SelfHealingVectorDb::new(config(tmp_dir.path()), None).expect("reopened");
+    assert_eq!(engine.health().size, 11);
+    assert_consistent(&mut engine);
+}
@@ -0,0 +1,112 @@
+"""Regenerate the tiny ONNX embedder fixture used by tests/embedder_onnx.rs.
+
+The model is a single Gather over a 4-dimensional embedding table, so every
+token maps to a fixed vector and pooling/normalisation can be checked by hand.
+Only the standard library is needed: the protobuf is encoded directly.
+"""
+
+import json
+import struct
+from pathlib import Path
+
+VOCAB = ["[PAD]", "[UNK]", "[CLS]", "cat", "dog", "fish", "car", "truck"]
+EMBEDDINGS = [
+    [0.0, 0.0, 0.0, 0.0],  # [PAD]
+    [0.0, 0.0, 0.0, 1.0],  # [UNK]
+    [0.0, 0.0, 1.0, 0.0],  # [CLS]
+    [1.0, 0.1, 0.0, 0.0],  # cat
+    [0.9, 0.2, 0.0, 0.0],  # dog
+    [0.8, 0.0, 0.0, 0.3],  # fish
+    [0.1, 1.0, 0.0, 0.0],  # car
+    [0.0, 0.9, 0.0, 0.2],  # truck
+]
+DIM = len(EMBEDDINGS[0])
+
+
+def varint(n):
+    out = bytearray()
+    while True:
+        b = n & 0x7F
+        n >>= 7
+        if n:
+            out.append(b | 0x80)
+        else:
+            out.append(b)
+            return bytes(out)
+
+
+def field(num, value):
+    if isinstance(value, int):
+        return varint(num << 3) + varint(value)
+    if isinstance(value, str):
+        value = value.encode()
+    return varint(num << 3 | 2) + varint(len(value)) + value
+
+
+def tensor_type(elem_type, dims):
+    shape = b"".join(
+        field(1, field(2, d) if isinstance(d, str) else field(1, d)) for d in dims
+    )
+    return field(1, field(1, elem_type) + field(2, shape))
+
+
+def value_info(name, elem_type, dims):
+    return field(1, name) + field(2, tensor_type(elem_type, dims))
+
+
+FLOAT, INT64 = 1, 7
+
+table = (
+    b"".join(field(1, d) for d in (len(VOCAB), DIM))
+    + field(2, FLOAT)
+    + field(8, "embeddings")
+    + field(9, struct.pack(f"<{len(VOCAB) * DIM}f", *sum(EMBEDDINGS, [])))
+)
+node = (
+    field(1, "embeddings")
+    + field(1, "input_ids")
+    + field(2, "last_hidden_state")
+    + field(3, "lookup")
+    + field(4, "Gather")
+)
+graph = (
+    field(1, node)
+    + field(2, "tiny-embedder")
+    + field(5, table)
+    + field(11, value_info("input_ids", INT64, ["batch", "seq"]))
+    + field(11, value_info("attention_mask", INT64, ["batch", "seq"]))
+    + field(12, value_info("last_hidden_state", FLOAT, ["batch", "seq", DIM]))
+)
+model = field(1, 8) + field(2, "generate.py") + field(7, graph) + field(8, field(2, 13))
+
+tokenizer = {
+    "version": "1.0",
+    "truncation": None,
+    "padding": None,
+    "added_tokens": [],
+    "normalizer": {"type": "Lowercase"},
+    "pre_tokenizer": {"type": "Whitespace"},
+    "post_processor": {
+        "type": "TemplateProcessing",
+        "single": [
+            {"SpecialToken": {"id": "[CLS]", "type_id": 0}},
+            {"Sequence": {"id": "A", "type_id": 0}},
+        ],
+        "pair": [
+            {"SpecialToken": {"id": "[CLS]", "type_id": 0}},
+            {"Sequence": {"id": "A", "type_id": 0}},
+            {"Sequence": {"id": "B", "type_id": 1}},
+        ],
+        "special_tokens": {"[CLS]": {"id": "[CLS]", "ids": [2], "tokens": ["[CLS]"]}},
+    },
+    "decoder": None,
+    "model": {
+        "type": "WordLevel",
+        "vocab": {token: i for i, token in enumerate(VOCAB)},
+        "unk_token": "[UNK]",
+    },
+}
+
+here = Path(__file__).parent
+(here / "model.onnx").write_bytes(model)
+(here / "tokenizer.json").write_text(json.dumps(tokenizer, indent=2) + "\n")
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+mod common;
+
 use std::sync::Arc;
 
 use axum::{
@@ -8,8 +9,9 @@ use axum::{
     routing::post,
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
+use common::test_config;
 use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde::{Deserialize, Serialize};
 use tempfile::tempdir;
 use tokio::sync::RwLock;
 use tower::ServiceExt;
@@ -37,10 +39,7 @@ struct SearchResult {
     distance: f32,
 }
 
-async fn add_handler(
-    State(state): State<AppState>,
-    Json(payload): Json<AddRequest>,
-) -> StatusCode {
+async fn add_handler(State(state): State<AppState>, Json(payload): Json<AddRequest>) -> StatusCode {
     let mut engine = state.engine.write().await;
     match engine.add_vectors(&payload.ids, &payload.vectors) {
         Ok(_) => StatusCode::OK,
@@ -53,9 +52,7 @@ async fn search_handler(
     Json(payload): Json<SearchRequest>,
 ) -> Json<Vec<SearchResult>> {
     let engine = state.engine.read().await;
-    let results = engine
-        .search(&payload.query, payload.k)
-        .unwrap_or_default();
+    let results = engine.search(&payload.query, payload.k).unwrap_or_default();
     let out = results
         .into_iter()
         .map(|r| SearchResult {
@@ -70,19 +67,12 @@ async fn search_handler(
 async fn http_add_and_search_roundtrip() {
     let dim = 4;
     let tmp_dir = tempdir().expect("tempdir");
-    let db_path: PathBuf = tmp_dir.path().join("vectors.sqlite");
-
     let cfg = EngineConfig {
         dim,
-        storage_path: db_path,
-        hnsw_max_elements: 10_000,
-        hnsw_m: 16,
-        hnsw_ef_construction: 200,
-        hnsw_ef_search: 64,
+        ..test_config(tmp_dir.path())
     };
 
-    let engine =
-        SelfHealingVectorDb::new(cfg, None).expect("engine created");
+    let engine = SelfHealingVectorDb::new(cfg, None).expect("engine created");
 
     let state = AppState {
         engine: Arc::new(RwLock::new(engine)),
@@ -135,11 +125,8 @@ async fn http_add_and_search_roundtrip() {
     let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
         .await
         .unwrap();
-    let body: Vec<SearchResult> =
-        serde_json::from_slice(&bytes).expect("valid JSON response");
+    let body: Vec<SearchResult> = serde_json::from_slice(&bytes).expect("valid JSON response");
 
     assert!(!body.is_empty(), "expected at least one search result");
     assert_eq!(body[0].id, 1, "nearest neighbor over HTTP should be id 1");
 }
-
-
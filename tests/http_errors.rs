@@ -0,0 +1,128 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use common::test_config;
+use self_healing_vector_db::api::{ApiError, ApiJson};
+use self_healing_vector_db::engine::{AddResult, SearchResult};
+use self_healing_vector_db::{EngineConfig, SelfHealingVectorDb};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+type SharedEngine = Arc<RwLock<SelfHealingVectorDb>>;
+
+#[derive(Debug, Deserialize)]
+struct AddRequest {
+    ids: Vec<i64>,
+    vectors: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchRequest {
+    query: Vec<f32>,
+    k: usize,
+}
+
+async fn add_handler(
+    State(engine): State<SharedEngine>,
+    ApiJson(payload): ApiJson<AddRequest>,
+) -> Result<Json<Vec<AddResult>>, ApiError> {
+    let mut engine = engine.write().await;
+    Ok(Json(engine.add_vectors(&payload.ids, &payload.vectors)?))
+}
+
+async fn search_handler(
+    State(engine): State<SharedEngine>,
+    ApiJson(payload): ApiJson<SearchRequest>,
+) -> Result<Json<Vec<SearchResult>>, ApiError> {
+    let engine = engine.read().await;
+    Ok(Json(engine.search(&payload.query, payload.k)?))
+}
+
+fn app(engine: SelfHealingVectorDb) -> Router {
+    Router::new()
+        .route("/add", post(add_handler))
+        .route("/search", post(search_handler))
+        .with_state(Arc::new(RwLock::new(engine)))
+}
+
+async fn post_json(app: &Router, uri: &str, body: String) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn errors_are_structured_and_distinct_from_empty_results() {
+    let tmp_dir = tempdir().expect("tempdir");
+    let cfg = EngineConfig {
+        dim: 3,
+        ..test_config(tmp_dir.path())
+    };
+    let app = app(SelfHealingVectorDb::new(cfg, None).expect("engine created"));
+
+    // An empty index is a successful, empty search.
+    let (status, body) = post_json(
+        &app,
+        "/search",
+        json!({"query": [1.0, 0.0, 0.0], "k": 5}).to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!([]));
+
+    // A query of the wrong dimension is a client error with details.
+    let (status, body) = post_json(
+        &app,
+        "/search",
+        json!({"query": [1.0, 0.0], "k": 5}).to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "dim_mismatch");
+    assert_eq!(body["error"]["details"], json!({"expected": 3, "got": 2}));
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("dimension"));
+
+    // A flat buffer that does not split into whole vectors.
+    let (status, body) = post_json(
+        &app,
+        "/add",
+        json!({"ids": [1, 2], "vectors": [1.0, 0.0, 0.0, 1.0, 0.0]}).to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "length_mismatch");
+    assert_eq!(body["error"]["details"]["expected"], 6);
+
+    // Malformed bodies are reported in the same shape.
+    let (status, body) = post_json(&app, "/search", "{not json".to_string()).await;
+    assert!(status.is_client_error());
+    assert_eq!(body["error"]["code"], "invalid_request");
+}